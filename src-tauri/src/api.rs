@@ -1,7 +1,79 @@
-use crate::error::{ApiErrorResponse, FetchError};
+use crate::config::{AppConfig, CredentialsConfig};
+use crate::error::{ApiErrorResponse, FetchError, NetworkErrorKind};
+use crate::http::{CookieJar, HttpConfig};
+use crate::provider::{MetricDescriptor, MetricRole, UsageProvider, UsageSnapshot};
+use crate::schema::{DriftRecorder, parse_usage};
 use crate::state::UsageData;
-use wreq::ClientBuilder;
-use wreq::header::{COOKIE, HeaderMap, HeaderValue, USER_AGENT};
+use chrono::{DateTime, Utc};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tracing::{info, warn};
+use wreq::Client;
+use wreq::header::{COOKIE, HeaderMap, HeaderValue, RETRY_AFTER, SET_COOKIE};
+
+/// Rate-limit reset headers consulted when `Retry-After` is absent
+const RATE_LIMIT_RESET_HEADERS: &[&str] = &[
+    "anthropic-ratelimit-requests-reset",
+    "anthropic-ratelimit-tokens-reset",
+    "anthropic-ratelimit-input-tokens-reset",
+    "anthropic-ratelimit-output-tokens-reset",
+    "x-ratelimit-reset",
+];
+
+/// Values above this are treated as Unix timestamps rather than delta-seconds
+const UNIX_TIMESTAMP_THRESHOLD: u64 = 1_000_000_000;
+
+/// Seconds from `now` until the given instant, saturating at zero
+fn secs_until(when: DateTime<Utc>, now: DateTime<Utc>) -> u64 {
+    (when - now).num_seconds().max(0) as u64
+}
+
+/// Parse a `Retry-After` value in either delta-seconds or HTTP-date form
+fn parse_retry_after_value(value: &str, now: DateTime<Utc>) -> Option<u64> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(seconds);
+    }
+
+    DateTime::parse_from_rfc2822(value)
+        .ok()
+        .map(|date| secs_until(date.with_timezone(&Utc), now))
+}
+
+/// Parse a rate-limit reset value (RFC 3339, Unix timestamp or delta-seconds)
+fn parse_reset_value(value: &str, now: DateTime<Utc>) -> Option<u64> {
+    let value = value.trim();
+    if let Ok(number) = value.parse::<u64>() {
+        return if number > UNIX_TIMESTAMP_THRESHOLD {
+            DateTime::from_timestamp(number as i64, 0).map(|date| secs_until(date, now))
+        } else {
+            Some(number)
+        };
+    }
+
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|date| secs_until(date.with_timezone(&Utc), now))
+}
+
+/// Determine how long the server asked us to wait after a 429, in seconds
+///
+/// `Retry-After` wins when present. Otherwise the latest of the known reset
+/// headers is used, so that waiting never lands before any exhausted limit resets.
+pub(crate) fn parse_retry_after(headers: &HeaderMap, now: DateTime<Utc>) -> Option<u64> {
+    let header_str = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+
+    if let Some(seconds) =
+        header_str(RETRY_AFTER.as_str()).and_then(|v| parse_retry_after_value(v, now))
+    {
+        return Some(seconds);
+    }
+
+    RATE_LIMIT_RESET_HEADERS
+        .iter()
+        .filter_map(|name| header_str(name).and_then(|v| parse_reset_value(v, now)))
+        .max()
+}
 
 /// Fetch usage data from the Claude API using a custom base URL (for testing)
 #[doc(hidden)]
@@ -10,43 +82,55 @@ pub async fn fetch_usage_data_with_base_url(
     org_id: &str,
     session_key: &str,
 ) -> Result<UsageData, FetchError> {
-    let mut headers = HeaderMap::new();
-    headers.insert(
-        COOKIE,
-        HeaderValue::from_str(&format!("sessionKey={}", session_key))
-            .map_err(|e| FetchError::Network(format!("Invalid header value: {}", e)))?,
-    );
-    headers.insert(
-        USER_AGENT,
-        HeaderValue::from_static("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36"),
-    );
+    let client = HttpConfig::default().build_client()?;
+    let cookies = Mutex::new(CookieJar::default());
+    let (_, body) = fetch_usage_body(&client, &cookies, base_url, org_id, session_key).await?;
+    parse_usage(&body).0
+}
 
-    let client = ClientBuilder::new()
-        .default_headers(headers)
-        .build()
-        .map_err(|e| FetchError::Network(format!("Failed to build client: {}", e)))?;
+/// Fetch the status and raw body of a successful usage response
+///
+/// Cookies the server sets are stored in `cookies`, whatever the status.
+async fn fetch_usage_body(
+    client: &Client,
+    cookies: &Mutex<CookieJar>,
+    base_url: &str,
+    org_id: &str,
+    session_key: &str,
+) -> Result<(u16, String), FetchError> {
+    let cookie = cookies.lock().unwrap().header(session_key, Utc::now());
+    let cookie = HeaderValue::from_str(&cookie)?;
 
     let url = format!("{}/api/organizations/{}/usage", base_url, org_id);
     let response = client
         .get(&url)
+        .header(COOKIE, cookie)
         .send()
         .await
-        .map_err(|e| FetchError::Network(format!("Failed to send request: {}", e)))?;
+        .map_err(|e| FetchError::request("send request", e))?;
 
     let status = response.status();
+    let now = Utc::now();
+    let retry_after = parse_retry_after(response.headers(), now);
+    cookies.lock().unwrap().store(
+        response
+            .headers()
+            .get_all(SET_COOKIE)
+            .iter()
+            .filter_map(|v| v.to_str().ok()),
+        now,
+    );
     let response_text = response
         .text()
         .await
-        .map_err(|e| FetchError::Network(format!("Failed to read response: {}", e)))?;
+        .map_err(|e| FetchError::request("read response", e))?;
 
     if status.is_success() {
-        serde_json::from_str::<UsageData>(&response_text)
-            .map_err(|e| FetchError::Parse(format!("Failed to parse response: {}", e)))
+        Ok((status.as_u16(), response_text))
     } else if status.as_u16() == 429 {
-        // Rate limited - basic detection without Retry-After parsing
         Err(FetchError::RateLimited {
             message: "Too many requests".to_string(),
-            retry_after: None,
+            retry_after,
         })
     } else if status.as_u16() == 401 || status.as_u16() == 403 {
         // Authentication/authorization errors
@@ -57,9 +141,12 @@ pub async fn fetch_usage_data_with_base_url(
             ),
             Err(_) => format!("HTTP {}", status),
         };
-        Err(FetchError::Auth(error_msg))
+        Err(FetchError::Auth {
+            status: Some(status.as_u16()),
+            message: error_msg,
+        })
     } else {
-        // Other errors (5xx, etc.) - treat as network/transient
+        // Other errors (5xx, etc.); whether to retry depends on the status
         let error_msg = match serde_json::from_str::<ApiErrorResponse>(&response_text) {
             Ok(error_data) => format!(
                 "{} - {}",
@@ -67,12 +154,196 @@ pub async fn fetch_usage_data_with_base_url(
             ),
             Err(_) => format!("HTTP {}", status),
         };
-        Err(FetchError::Network(error_msg))
+        Err(FetchError::network(
+            NetworkErrorKind::HttpStatus(status.as_u16()),
+            error_msg,
+        ))
     }
 }
 
-pub async fn fetch_usage_data() -> Result<UsageData, FetchError> {
-    let org_id = std::env::var("CLAUDE_ORG_ID")?;
-    let session_key = std::env::var("CLAUDE_SESSION_KEY")?;
-    fetch_usage_data_with_base_url("https://claude.ai", &org_id, &session_key).await
+const CLAUDE_BASE_URL: &str = "https://claude.ai";
+
+const CLAUDE_METRICS: &[MetricDescriptor] = &[
+    MetricDescriptor {
+        id: "seven_day",
+        label: "Weekly",
+        role: MetricRole::LongWindow,
+    },
+    MetricDescriptor {
+        id: "five_hour",
+        label: "5-hour",
+        role: MetricRole::ShortWindow,
+    },
+    MetricDescriptor {
+        id: "seven_day_opus",
+        label: "Opus (weekly)",
+        role: MetricRole::Auxiliary,
+    },
+    MetricDescriptor {
+        id: "seven_day_oauth_apps",
+        label: "OAuth apps (weekly)",
+        role: MetricRole::Auxiliary,
+    },
+];
+
+/// Usage provider for the Claude web API
+///
+/// Clones share one HTTP client, so connections are reused between polls.
+#[derive(Debug, Clone)]
+pub struct ClaudeProvider {
+    base_url: String,
+    credentials: CredentialsConfig,
+    drift: Arc<Mutex<DriftRecorder>>,
+    http: HttpConfig,
+    /// Built on the first fetch and reused afterwards
+    client: Arc<Mutex<Option<Client>>>,
+    cookies: Arc<Mutex<CookieJar>>,
+}
+
+impl ClaudeProvider {
+    /// Create a provider that keeps cookies and samples of unexpected
+    /// responses in the data directory
+    pub fn new(credentials: CredentialsConfig, http: HttpConfig) -> Self {
+        Self::with_base_url(CLAUDE_BASE_URL, credentials)
+            .with_drift_samples(DriftRecorder::default_sample_dir())
+            .with_http_config(http)
+    }
+
+    /// Create a provider pointing at a custom base URL (for testing)
+    ///
+    /// Schema drift is only logged and cookies are kept in memory; see
+    /// `with_drift_samples` and `with_http_config`.
+    pub fn with_base_url(base_url: impl Into<String>, credentials: CredentialsConfig) -> Self {
+        Self {
+            base_url: base_url.into(),
+            credentials,
+            drift: Arc::default(),
+            http: HttpConfig::default(),
+            client: Arc::default(),
+            cookies: Arc::default(),
+        }
+    }
+
+    /// Save a sample of each new response shape to `dir`
+    pub fn with_drift_samples(mut self, dir: Option<PathBuf>) -> Self {
+        self.drift = Arc::new(Mutex::new(DriftRecorder::new(dir)));
+        self
+    }
+
+    /// Use `http` for the client and load its cookie file
+    pub fn with_http_config(mut self, http: HttpConfig) -> Self {
+        self.cookies = Arc::new(Mutex::new(CookieJar::open(http.resolved_cookie_path())));
+        self.client = Arc::default();
+        self.http = http;
+        self
+    }
+
+    /// The shared client, building it on first use
+    fn client(&self) -> Result<Client, FetchError> {
+        let mut client = self.client.lock().unwrap();
+        if let Some(client) = client.as_ref() {
+            return Ok(client.clone());
+        }
+        let built = self.http.build_client()?;
+        *client = Some(built.clone());
+        Ok(built)
+    }
+}
+
+impl UsageProvider for ClaudeProvider {
+    fn id(&self) -> &'static str {
+        "claude"
+    }
+
+    fn display_name(&self) -> &str {
+        "Claude"
+    }
+
+    fn metrics(&self) -> &[MetricDescriptor] {
+        CLAUDE_METRICS
+    }
+
+    fn reconfigure(&mut self, config: &AppConfig) {
+        self.credentials = config.credentials.clone();
+        if config.http != self.http {
+            info!("HTTP settings changed, rebuilding the client");
+            if config.http.resolved_cookie_path() != self.http.resolved_cookie_path() {
+                self.cookies = Arc::new(Mutex::new(CookieJar::open(
+                    config.http.resolved_cookie_path(),
+                )));
+            }
+            // Built again with the new settings on the next fetch
+            self.client = Arc::default();
+            self.http = config.http.clone();
+        }
+    }
+
+    async fn fetch(&self) -> Result<UsageSnapshot, FetchError> {
+        let org_id = required(&self.credentials.org_id, "credentials.org_id")?;
+        let session_key = required(&self.credentials.session_key, "credentials.session_key")?;
+        if org_id.contains(|c: char| c == '/' || c.is_whitespace()) {
+            return Err(FetchError::Config {
+                key: "credentials.org_id".to_string(),
+                message: "must not contain '/' or whitespace".to_string(),
+            });
+        }
+        if HeaderValue::from_str(session_key).is_err() || session_key.contains(';') {
+            return Err(FetchError::Config {
+                key: "credentials.session_key".to_string(),
+                message: "contains characters that can't be sent in a cookie".to_string(),
+            });
+        }
+
+        let client = self.client()?;
+        let (status, body) =
+            fetch_usage_body(&client, &self.cookies, &self.base_url, org_id, session_key).await?;
+        let (data, report) = parse_usage(&body);
+        self.drift.lock().unwrap().observe(&report, &body);
+        Ok(UsageSnapshot {
+            http_status: Some(status),
+            ..data?.into()
+        })
+    }
+}
+
+/// A credential that must be set and non-empty
+fn required<'a>(value: &'a Option<String>, key: &str) -> Result<&'a str, FetchError> {
+    value
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .ok_or_else(|| FetchError::missing_config(key))
+}
+
+impl From<UsageData> for UsageSnapshot {
+    fn from(data: UsageData) -> Self {
+        let other: Vec<_> = data
+            .other_periods()
+            .map(|(id, period)| (id.to_string(), period))
+            .collect();
+        let periods = [
+            ("five_hour", Some(data.five_hour)),
+            ("seven_day", Some(data.seven_day)),
+            ("seven_day_oauth_apps", data.seven_day_oauth_apps),
+            ("seven_day_opus", data.seven_day_opus),
+            ("iguana_necktie", data.iguana_necktie),
+        ]
+        .into_iter()
+        .filter_map(|(id, period)| period.map(|p| (id.to_string(), p)))
+        .chain(other)
+        .map(|(id, mut period)| {
+            // Negative usage is meaningless; treat it as unused rather than failing the fetch
+            if period.utilization < 0.0 {
+                warn!(metric = %id, utilization = period.utilization, "Negative utilization, treating as 0");
+                period.utilization = 0.0;
+            }
+            (id, period)
+        })
+        .collect();
+
+        Self {
+            periods,
+            http_status: None,
+        }
+    }
 }
@@ -1,46 +1,106 @@
+use crate::api::ClaudeProvider;
+use crate::cli::CliArgs;
+use crate::commands::command_channel;
+use crate::config::{AppConfig, ConfigLoader, default_config_path};
+use crate::error::ErrorIndicator;
 use crate::icon::generate_unknown_icon;
 use crate::polling::start_polling;
+use crate::simulate;
+use crate::tray::{ConfigPath, Diagnostics, StatusText, build_menu, handle_menu_event};
+use std::path::Path;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use tauri::Manager;
-use tauri::menu::{MenuBuilder, MenuItemBuilder};
 use tauri::tray::TrayIconBuilder;
 use tokio_util::sync::CancellationToken;
-use tracing::info;
+use tracing::{error, info};
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    // Initialize tracing
+    // Load environment variables from .env file in repository root (development fallback)
+    dotenvy::from_filename("../.env").ok();
+
+    let args = match CliArgs::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            attach_parent_console();
+            eprintln!("{}\n\n{}", e, CliArgs::usage());
+            std::process::exit(2);
+        }
+    };
+    if args.help || args.simulate.is_some() {
+        attach_parent_console();
+    }
+    if args.help {
+        println!("{}", CliArgs::usage());
+        return;
+    }
+
+    // Initialize tracing; the simulator only reports warnings so its output stays readable
+    let default_filter = if args.simulate.is_some() {
+        "warn"
+    } else {
+        "info"
+    };
     tracing_subscriber::fmt()
         .with_env_filter(
             tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info")),
+                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new(default_filter)),
         )
         .init();
 
-    // Load environment variables from .env file in repository root
-    dotenvy::from_filename("../.env").ok();
+    // Layer configuration: file < environment < command line
+    let loader = ConfigLoader::new(args.config_path.or_else(default_config_path))
+        .with_process_env()
+        .with_cli(args.overrides);
+    let (config, config_warning) = match loader.load() {
+        Ok(config) => {
+            info!(path = ?loader.path(), "Configuration loaded");
+            (config, None)
+        }
+        Err(e) if args.simulate.is_some() => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+        Err(e) => {
+            // Keep the tray up so the problem is visible; fixing the file reloads it
+            error!("Invalid configuration, starting with defaults: {}", e);
+            (AppConfig::default(), Some(e.summary()))
+        }
+    };
+
+    if let Some(simulate_args) = &args.simulate {
+        match simulate::run(simulate_args, &config) {
+            Ok(report) => println!("{}", report),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
 
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
         .setup(|app| {
-            // Create tray menu
-            let quit_item = MenuItemBuilder::with_id("quit", "Quit").build(app)?;
-
-            let menu = MenuBuilder::new(app).item(&quit_item).build()?;
+            // Create tray menu; it is rebuilt with live status on every update
+            let menu = build_menu(
+                app.handle(),
+                &["No data available yet".to_string()],
+                &[],
+                None,
+                false,
+            )?;
 
             // Create initial tray icon with unknown state
-            let icon_bytes = generate_unknown_icon();
+            let icon_bytes = generate_unknown_icon(ErrorIndicator::None);
             let icon = tauri::image::Image::new_owned(icon_bytes, 32, 32);
 
             TrayIconBuilder::with_id("main")
                 .icon(icon)
                 .menu(&menu)
-                .on_menu_event(|app, event| {
-                    if event.id.as_ref() == "quit" {
-                        app.exit(0);
-                    }
-                })
+                .on_menu_event(|app, event| handle_menu_event(app, event.id.as_ref()))
                 .build(app)?;
 
             info!("Tray icon created successfully");
@@ -52,9 +112,25 @@ pub fn run() {
             // Create shutdown flag to prevent infinite exit loop
             let shutdown_started = Arc::new(AtomicBool::new(false));
 
+            // Menu actions reach the polling loop through the command channel
+            let (command_tx, command_rx) = command_channel();
+            app.manage(command_tx);
+            app.manage(ConfigPath(loader.path().map(Path::to_path_buf)));
+            app.manage(StatusText::default());
+            app.manage(Diagnostics::default());
+
             // Start background polling task
             let app_handle = app.handle().clone();
-            tauri::async_runtime::spawn(start_polling(app_handle, cancel_clone));
+            let provider = ClaudeProvider::new(config.credentials.clone(), config.http.clone());
+            tauri::async_runtime::spawn(start_polling(
+                app_handle,
+                provider,
+                config,
+                config_warning,
+                loader,
+                command_rx,
+                cancel_clone,
+            ));
 
             // Store state for shutdown handling
             app.manage(cancel_token);
@@ -90,3 +166,19 @@ pub fn run() {
             }
         });
 }
+
+/// Send console output to the terminal the app was started from
+///
+/// Release builds on Windows use the GUI subsystem and start without a
+/// console, so `--help` and `simulate` would otherwise print nowhere.
+#[cfg(windows)]
+fn attach_parent_console() {
+    use windows::Win32::System::Console::{ATTACH_PARENT_PROCESS, AttachConsole};
+
+    // SAFETY: AttachConsole has no preconditions; it fails harmlessly when
+    // there is no parent console or one is already attached
+    let _ = unsafe { AttachConsole(ATTACH_PARENT_PROCESS) };
+}
+
+#[cfg(not(windows))]
+fn attach_parent_console() {}
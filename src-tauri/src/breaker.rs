@@ -0,0 +1,187 @@
+use crate::error::FetchError;
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Configuration for the circuit breaker around permanent failures
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BreakerConfig {
+    /// Consecutive permanent failures before the breaker opens
+    pub failure_threshold: u32,
+    /// Time between probes while the breaker is open
+    pub probe_interval_secs: u64,
+}
+
+impl Default for BreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            probe_interval_secs: 1800, // 30 minutes
+        }
+    }
+}
+
+/// Where the circuit breaker stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerState {
+    /// Fetching on the usual schedule
+    Closed,
+    /// Permanent failures keep happening; only probing every `probe_interval_secs`
+    Open,
+    /// A probe is in flight; its result closes or re-opens the breaker
+    HalfOpen,
+}
+
+impl BreakerState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BreakerState::Closed => "closed",
+            BreakerState::Open => "open",
+            BreakerState::HalfOpen => "half-open",
+        }
+    }
+}
+
+/// Stops retrying errors that won't clear up on their own
+///
+/// `RetryState` retries permanent errors (auth, parse) at its minimum delay.
+/// After `failure_threshold` of them in a row the breaker opens and stretches
+/// the delay to `probe_interval_secs`. The next fetch is a probe: success
+/// closes the breaker, failure opens it again. A config change also closes it.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    config: BreakerConfig,
+    state: BreakerState,
+    permanent_failures: u32,
+}
+
+impl CircuitBreaker {
+    pub fn new(config: BreakerConfig) -> Self {
+        Self {
+            config,
+            state: BreakerState::Closed,
+            permanent_failures: 0,
+        }
+    }
+
+    pub fn state(&self) -> BreakerState {
+        self.state
+    }
+
+    pub fn update_config(&mut self, config: BreakerConfig) {
+        self.config = config;
+    }
+
+    /// Mark a fetch as started, turning it into a probe if the breaker is open
+    pub fn fetch_started(&mut self) {
+        if self.state == BreakerState::Open {
+            self.state = BreakerState::HalfOpen;
+        }
+    }
+
+    /// Record a successful fetch, returning whether the breaker was not closed
+    pub fn record_success(&mut self) -> bool {
+        self.reset()
+    }
+
+    /// Record a failure, stretching `retry_delay` to the probe interval while open
+    pub fn record_failure(&mut self, error: &FetchError, retry_delay: Duration) -> Duration {
+        if error.is_transient() {
+            self.permanent_failures = 0;
+        } else {
+            self.permanent_failures += 1;
+        }
+
+        // A failed probe re-opens the breaker whatever the error was
+        if self.state == BreakerState::HalfOpen
+            || self.permanent_failures >= self.config.failure_threshold
+        {
+            self.state = BreakerState::Open;
+        }
+
+        self.delay(retry_delay)
+    }
+
+    /// Actual wait before the next fetch: at least the probe interval while open
+    pub fn delay(&self, retry_delay: Duration) -> Duration {
+        match self.state {
+            BreakerState::Open => {
+                retry_delay.max(Duration::from_secs(self.config.probe_interval_secs))
+            }
+            _ => retry_delay,
+        }
+    }
+
+    /// Close the breaker, returning whether it was open or half-open
+    pub fn reset(&mut self) -> bool {
+        let was_tripped = self.state != BreakerState::Closed;
+        self.state = BreakerState::Closed;
+        self.permanent_failures = 0;
+        was_tripped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::NetworkErrorKind;
+    use assert2::assert;
+
+    const RETRY: Duration = Duration::from_secs(5);
+    const PROBE: Duration = Duration::from_secs(1800);
+
+    fn auth() -> FetchError {
+        FetchError::auth("Invalid session key")
+    }
+
+    #[test]
+    fn test_opens_after_consecutive_permanent_failures() {
+        let mut breaker = CircuitBreaker::new(BreakerConfig::default());
+
+        for _ in 0..4 {
+            breaker.fetch_started();
+            assert!(breaker.record_failure(&auth(), RETRY) == RETRY);
+            assert!(breaker.state() == BreakerState::Closed);
+        }
+        breaker.fetch_started();
+        assert!(breaker.record_failure(&auth(), RETRY) == PROBE);
+        assert!(breaker.state() == BreakerState::Open);
+    }
+
+    #[test]
+    fn test_transient_failures_break_the_streak() {
+        let mut breaker = CircuitBreaker::new(BreakerConfig::default());
+        let timeout = FetchError::network(NetworkErrorKind::Timeout, "timed out");
+
+        for _ in 0..4 {
+            breaker.record_failure(&auth(), RETRY);
+        }
+        assert!(breaker.record_failure(&timeout, RETRY) == RETRY);
+        for _ in 0..4 {
+            breaker.record_failure(&auth(), RETRY);
+        }
+        assert!(breaker.state() == BreakerState::Closed);
+    }
+
+    #[test]
+    fn test_probe_closes_or_reopens() {
+        let mut breaker = CircuitBreaker::new(BreakerConfig {
+            failure_threshold: 1,
+            ..BreakerConfig::default()
+        });
+        breaker.record_failure(&auth(), RETRY);
+        assert!(breaker.state() == BreakerState::Open);
+
+        // Any failed probe re-opens, even a transient one
+        breaker.fetch_started();
+        assert!(breaker.state() == BreakerState::HalfOpen);
+        let timeout = FetchError::network(NetworkErrorKind::Timeout, "timed out");
+        assert!(breaker.record_failure(&timeout, RETRY) == PROBE);
+        assert!(breaker.state() == BreakerState::Open);
+
+        breaker.fetch_started();
+        assert!(breaker.record_success());
+        assert!(breaker.state() == BreakerState::Closed);
+        assert!(!breaker.reset());
+    }
+}
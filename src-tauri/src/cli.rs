@@ -0,0 +1,155 @@
+use crate::simulate::SimulateArgs;
+use std::path::PathBuf;
+
+const USAGE: &str = "\
+Usage: usage-indicator [OPTIONS]
+       usage-indicator simulate <TRACE> [--compare <PATH>] [OPTIONS]
+
+Commands:
+  simulate <TRACE>        Replay a CSV or JSON usage trace through the poller offline
+
+Options:
+  -c, --config <PATH>     Path to the config file
+  -s, --set <KEY=VALUE>   Override a config key (e.g. --set poller.min_interval_secs=120)
+      --compare <PATH>    Simulate a second config file side by side
+  -h, --help              Print this help";
+
+/// Parsed command line arguments
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CliArgs {
+    /// Explicit config file path (defaults to the XDG config location)
+    pub config_path: Option<PathBuf>,
+    /// Config overrides as (dotted key, value) pairs, highest precedence
+    pub overrides: Vec<(String, String)>,
+    pub help: bool,
+    /// Run the offline simulator instead of the tray app
+    pub simulate: Option<SimulateArgs>,
+}
+
+impl CliArgs {
+    /// Parse arguments, excluding the program name
+    pub fn parse<I>(args: I) -> Result<Self, String>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter();
+        let mut simulating = false;
+        let mut trace = None;
+        let mut compare = None;
+
+        while let Some(arg) = args.next() {
+            // Support both `--flag value` and `--flag=value`
+            let (flag, inline_value) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => {
+                    (flag.to_string(), Some(value.to_string()))
+                }
+                _ => (arg.clone(), None),
+            };
+            let mut value = |name: &str| {
+                inline_value
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| format!("Missing value for {}", name))
+            };
+
+            match flag.as_str() {
+                "-c" | "--config" => parsed.config_path = Some(PathBuf::from(value(&flag)?)),
+                "-s" | "--set" => {
+                    let assignment = value(&flag)?;
+                    let (key, val) = assignment
+                        .split_once('=')
+                        .ok_or_else(|| format!("Expected KEY=VALUE, got '{}'", assignment))?;
+                    parsed
+                        .overrides
+                        .push((key.trim().to_string(), val.trim().to_string()));
+                }
+                "--compare" => compare = Some(PathBuf::from(value(&flag)?)),
+                "-h" | "--help" => parsed.help = true,
+                "simulate" if !simulating => simulating = true,
+                other if simulating && trace.is_none() && !other.starts_with('-') => {
+                    trace = Some(PathBuf::from(other));
+                }
+                other => return Err(format!("Unknown argument '{}'", other)),
+            }
+        }
+
+        if simulating {
+            let trace = trace.ok_or("simulate needs a trace file")?;
+            parsed.simulate = Some(SimulateArgs { trace, compare });
+        } else if compare.is_some() {
+            return Err("--compare only applies to simulate".to_string());
+        }
+
+        Ok(parsed)
+    }
+
+    pub fn usage() -> &'static str {
+        USAGE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::{assert, let_assert};
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_config_and_overrides() {
+        let_assert!(
+            Ok(parsed) = CliArgs::parse(args(&[
+                "--config",
+                "/tmp/config.toml",
+                "--set",
+                "poller.min_interval_secs=120",
+                "--set=retry.multiplier = 1.5",
+            ]))
+        );
+
+        assert!(parsed.config_path == Some(PathBuf::from("/tmp/config.toml")));
+        assert!(
+            parsed.overrides
+                == vec![
+                    ("poller.min_interval_secs".to_string(), "120".to_string()),
+                    ("retry.multiplier".to_string(), "1.5".to_string()),
+                ]
+        );
+    }
+
+    #[test]
+    fn test_parse_simulate() {
+        let_assert!(
+            Ok(parsed) = CliArgs::parse(args(&[
+                "simulate",
+                "trace.csv",
+                "--compare",
+                "budget.toml",
+                "--set",
+                "poller.min_interval_secs=120",
+            ]))
+        );
+
+        assert!(
+            parsed.simulate
+                == Some(SimulateArgs {
+                    trace: PathBuf::from("trace.csv"),
+                    compare: Some(PathBuf::from("budget.toml")),
+                })
+        );
+        assert!(parsed.overrides.len() == 1);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(CliArgs::parse(args(&["--config"])).is_err());
+        assert!(CliArgs::parse(args(&["--set", "no-equals"])).is_err());
+        assert!(CliArgs::parse(args(&["--bogus"])).is_err());
+        assert!(CliArgs::parse(args(&["simulate"])).is_err());
+        assert!(CliArgs::parse(args(&["simulate", "a.csv", "b.csv"])).is_err());
+        assert!(CliArgs::parse(args(&["--compare", "other.toml"])).is_err());
+    }
+}
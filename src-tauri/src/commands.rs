@@ -0,0 +1,232 @@
+use crate::events::PollAction;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+/// Commands sent to the polling loop (from the tray menu)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PollCommand {
+    /// Fetch immediately, even while paused
+    RefreshNow,
+
+    /// Stop polling for a duration, or until resumed when `None`
+    Pause(Option<Duration>),
+
+    /// Resume polling after a pause
+    Resume,
+
+    /// Forget accumulated failures and retry right away if backing off
+    ResetBackoff,
+
+    /// Re-read the config file without waiting for the file watcher
+    ReloadConfig,
+}
+
+pub type CommandSender = mpsc::UnboundedSender<PollCommand>;
+pub type CommandReceiver = mpsc::UnboundedReceiver<PollCommand>;
+
+/// Create the channel between the tray menu and the polling loop
+pub fn command_channel() -> (CommandSender, CommandReceiver) {
+    mpsc::unbounded_channel()
+}
+
+/// When the polling loop should fetch next
+///
+/// The loop sleeps until `next_fetch_at` inside a `select!` alongside the
+/// command channel. Any command or system event wakes it, updates the schedule
+/// and re-enters the select with the new deadline, so an interrupted sleep is
+/// never lost or restarted from scratch.
+#[derive(Debug, Clone)]
+pub struct PollSchedule {
+    next_fetch_at: Instant,
+    /// Paused by a system event (sleep, logout, screen off)
+    system_paused: bool,
+    /// Paused from the tray menu
+    user_paused: bool,
+    pause_expires_at: Option<Instant>,
+    /// Fetch once at `next_fetch_at` even while paused
+    force_fetch: bool,
+    /// Stopped until the configuration changes
+    awaiting_config: bool,
+}
+
+impl PollSchedule {
+    /// Start with an immediate fetch
+    pub fn new(now: Instant) -> Self {
+        Self {
+            next_fetch_at: now,
+            system_paused: false,
+            user_paused: false,
+            pause_expires_at: None,
+            force_fetch: false,
+            awaiting_config: false,
+        }
+    }
+
+    pub fn next_fetch_at(&self) -> Instant {
+        self.next_fetch_at
+    }
+
+    /// Whether the fetch deadline should currently be waited on
+    pub fn fetch_enabled(&self) -> bool {
+        self.force_fetch || !(self.system_paused || self.user_paused || self.awaiting_config)
+    }
+
+    /// Deadline of a timed user pause, if any
+    pub fn pause_expires_at(&self) -> Option<Instant> {
+        self.pause_expires_at
+    }
+
+    pub fn is_user_paused(&self) -> bool {
+        self.user_paused
+    }
+
+    pub fn is_system_paused(&self) -> bool {
+        self.system_paused
+    }
+
+    pub fn is_awaiting_config(&self) -> bool {
+        self.awaiting_config
+    }
+
+    /// Stop fetching until `config_changed`; a manual refresh still fetches once
+    pub fn await_config(&mut self) {
+        self.awaiting_config = true;
+    }
+
+    /// Fetch right away if polling was waiting for a config change
+    pub fn config_changed(&mut self, now: Instant) {
+        if self.awaiting_config {
+            self.awaiting_config = false;
+            self.next_fetch_at = now;
+        }
+    }
+
+    /// Fetch at `now` unless paused
+    pub fn retry_now(&mut self, now: Instant) {
+        self.next_fetch_at = now;
+    }
+
+    /// Fetch as soon as possible, bypassing any pause once
+    pub fn fetch_now(&mut self, now: Instant) {
+        self.force_fetch = true;
+        self.next_fetch_at = now;
+    }
+
+    /// Apply the scheduling side of a command
+    ///
+    /// `ResetBackoff` and `ReloadConfig` need other loop state, so the loop
+    /// handles them itself and calls `fetch_now` as needed.
+    pub fn apply_command(&mut self, command: PollCommand, now: Instant) {
+        match command {
+            PollCommand::RefreshNow => self.fetch_now(now),
+            PollCommand::Pause(duration) => {
+                self.user_paused = true;
+                self.pause_expires_at = duration.map(|d| now + d);
+            }
+            PollCommand::Resume => self.resume(now),
+            PollCommand::ResetBackoff | PollCommand::ReloadConfig => {}
+        }
+    }
+
+    /// Apply the action recommended for a system event
+    pub fn apply_system_action(&mut self, action: PollAction, now: Instant) {
+        match action {
+            PollAction::Pause => self.system_paused = true,
+            PollAction::FetchImmediately => {
+                self.system_paused = false;
+                // A user pause still holds; the fetch happens when it ends
+                self.next_fetch_at = now;
+            }
+            PollAction::Continue => {}
+        }
+    }
+
+    /// End a user pause and fetch right away
+    pub fn resume(&mut self, now: Instant) {
+        self.user_paused = false;
+        self.pause_expires_at = None;
+        self.next_fetch_at = now;
+    }
+
+    /// Mark the pending fetch as started
+    pub fn fetch_started(&mut self) {
+        self.force_fetch = false;
+    }
+
+    /// Schedule the next fetch `delay` after `now`
+    pub fn fetch_finished(&mut self, delay: Duration, now: Instant) {
+        self.next_fetch_at = now + delay;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::assert;
+
+    #[test]
+    fn test_reload_keeps_deadline() {
+        let now = Instant::now();
+        let mut schedule = PollSchedule::new(now);
+        schedule.fetch_finished(Duration::from_secs(600), now);
+
+        // ReloadConfig wakes the loop but doesn't touch the schedule
+        schedule.apply_command(PollCommand::ReloadConfig, now + Duration::from_secs(30));
+        assert!(schedule.fetch_enabled());
+        assert!(schedule.next_fetch_at() == now + Duration::from_secs(600));
+    }
+
+    #[test]
+    fn test_refresh_while_paused_fetches_once() {
+        let now = Instant::now();
+        let mut schedule = PollSchedule::new(now);
+        schedule.apply_command(PollCommand::Pause(None), now);
+        assert!(!schedule.fetch_enabled());
+
+        schedule.apply_command(PollCommand::RefreshNow, now);
+        assert!(schedule.fetch_enabled());
+
+        schedule.fetch_started();
+        schedule.fetch_finished(Duration::from_secs(300), now);
+        assert!(!schedule.fetch_enabled());
+        assert!(schedule.is_user_paused());
+    }
+
+    #[test]
+    fn test_awaiting_config_until_it_changes() {
+        let now = Instant::now();
+        let mut schedule = PollSchedule::new(now);
+        schedule.fetch_finished(Duration::from_secs(5), now);
+        schedule.await_config();
+        assert!(!schedule.fetch_enabled());
+
+        // A manual refresh still tries once
+        schedule.apply_command(PollCommand::RefreshNow, now);
+        assert!(schedule.fetch_enabled());
+        schedule.fetch_started();
+        schedule.fetch_finished(Duration::from_secs(5), now);
+        assert!(!schedule.fetch_enabled());
+
+        let later = now + Duration::from_secs(60);
+        schedule.config_changed(later);
+        assert!(schedule.fetch_enabled());
+        assert!(schedule.next_fetch_at() == later);
+    }
+
+    #[test]
+    fn test_wake_does_not_override_user_pause() {
+        let now = Instant::now();
+        let mut schedule = PollSchedule::new(now);
+        schedule.apply_command(PollCommand::Pause(None), now);
+        schedule.apply_system_action(PollAction::Pause, now);
+
+        schedule.apply_system_action(PollAction::FetchImmediately, now);
+        assert!(!schedule.is_system_paused());
+        assert!(!schedule.fetch_enabled());
+
+        schedule.apply_command(PollCommand::Resume, now);
+        assert!(schedule.fetch_enabled());
+        assert!(schedule.next_fetch_at() == now);
+    }
+}
@@ -0,0 +1,872 @@
+use crate::breaker::BreakerConfig;
+use crate::history::HistoryConfig;
+use crate::http::HttpConfig;
+use crate::icon::IconConfig;
+use crate::notify::NotificationConfig;
+use crate::poller::PollerConfig;
+use crate::retry::RetryConfig;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use toml::de::{DeTable, DeValue};
+use toml::{Table, Value};
+use tracing::debug;
+
+const APP_DIR_NAME: &str = "usage-indicator";
+const CONFIG_FILE_NAME: &str = "config.toml";
+
+/// Environment variables that override config keys (`section.key`)
+const ENV_OVERRIDES: &[(&str, &str)] = &[
+    ("POLL_STRATEGY", "poller.strategy"),
+    ("POLL_MIN_INTERVAL_SECS", "poller.min_interval_secs"),
+    ("POLL_MAX_INTERVAL_SECS", "poller.max_interval_secs"),
+    (
+        "POLL_ADDITIVE_INCREASE_SECS",
+        "poller.additive_increase_secs",
+    ),
+    ("POLL_RECENCY_WINDOW_SECS", "poller.recency_window_secs"),
+    ("POLL_CONTEXT_WINDOW_SECS", "poller.context_window_secs"),
+    ("POLL_IDLE_TO_COLD_SECS", "poller.idle_to_cold_secs"),
+    ("POLL_RESET_GRACE_SECS", "poller.reset_grace_secs"),
+    (
+        "POLL_FIVE_HOUR_SUSTAINED_THRESHOLD",
+        "poller.five_hour_sustained_threshold",
+    ),
+    (
+        "POLL_WEEKLY_SUSTAINED_THRESHOLD",
+        "poller.weekly_sustained_threshold",
+    ),
+    (
+        "POLL_FIVE_HOUR_RECENT_THRESHOLD",
+        "poller.five_hour_recent_threshold",
+    ),
+    ("POLL_WARM_MULTIPLIER", "poller.warm_multiplier"),
+    ("POLL_HOT_MULTIPLIER", "poller.hot_multiplier"),
+    ("POLL_BLAZING_MULTIPLIER", "poller.blazing_multiplier"),
+    ("POLL_FIXED_INTERVAL_SECS", "poller.fixed_interval_secs"),
+    ("POLL_IDLE_DOUBLING_SECS", "poller.idle_doubling_secs"),
+    ("POLL_DAILY_REQUESTS", "poller.daily_requests"),
+    ("POLL_JITTER", "poller.jitter"),
+    ("RETRY_MIN_DELAY_SECS", "retry.min_delay_secs"),
+    ("RETRY_MAX_DELAY_SECS", "retry.max_delay_secs"),
+    ("RETRY_MULTIPLIER", "retry.multiplier"),
+    ("RETRY_JITTER", "retry.jitter"),
+    ("BREAKER_FAILURE_THRESHOLD", "breaker.failure_threshold"),
+    ("BREAKER_PROBE_INTERVAL_SECS", "breaker.probe_interval_secs"),
+    ("ICON_METRIC", "icon.metric"),
+    ("ICON_DECIMALS", "icon.decimals"),
+    (
+        "ICON_STALENESS_THRESHOLD_SECS",
+        "icon.staleness_threshold_secs",
+    ),
+    ("HISTORY_ENABLED", "history.enabled"),
+    ("HISTORY_RETENTION_DAYS", "history.retention_days"),
+    ("HISTORY_PATH", "history.path"),
+    ("NOTIFY_ENABLED", "notifications.enabled"),
+    ("NOTIFY_THRESHOLDS", "notifications.thresholds"),
+    ("NOTIFY_HYSTERESIS", "notifications.hysteresis"),
+    (
+        "NOTIFY_AUTH_FAILURE_THRESHOLD",
+        "notifications.auth_failure_threshold",
+    ),
+    ("NOTIFY_ON_RESET", "notifications.notify_on_reset"),
+    ("HTTP_CONNECT_TIMEOUT_SECS", "http.connect_timeout_secs"),
+    ("HTTP_TIMEOUT_SECS", "http.timeout_secs"),
+    ("HTTP_KEEP_ALIVE_SECS", "http.keep_alive_secs"),
+    ("HTTP_PERSIST_COOKIES", "http.persist_cookies"),
+    ("HTTP_COOKIE_PATH", "http.cookie_path"),
+    ("CLAUDE_ORG_ID", "credentials.org_id"),
+    ("CLAUDE_SESSION_KEY", "credentials.session_key"),
+];
+
+/// Environment variable that overrides `key`, if any
+pub fn env_var_for(key: &str) -> Option<&'static str> {
+    ENV_OVERRIDES
+        .iter()
+        .find(|(_, k)| *k == key)
+        .map(|(var, _)| *var)
+}
+
+/// Credentials used to authenticate against the usage API
+#[derive(Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CredentialsConfig {
+    pub org_id: Option<String>,
+    pub session_key: Option<String>,
+}
+
+impl std::fmt::Debug for CredentialsConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Never log the session key itself
+        f.debug_struct("CredentialsConfig")
+            .field("org_id", &self.org_id)
+            .field(
+                "session_key",
+                &self.session_key.as_ref().map(|_| "<redacted>"),
+            )
+            .finish()
+    }
+}
+
+/// Complete application configuration
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AppConfig {
+    pub poller: PollerConfig,
+    pub retry: RetryConfig,
+    pub breaker: BreakerConfig,
+    pub icon: IconConfig,
+    pub history: HistoryConfig,
+    pub notifications: NotificationConfig,
+    pub http: HttpConfig,
+    pub credentials: CredentialsConfig,
+}
+
+/// Where a configuration value came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigOrigin {
+    File { path: PathBuf, line: Option<usize> },
+    Env(String),
+    Cli,
+}
+
+impl std::fmt::Display for ConfigOrigin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigOrigin::File {
+                path,
+                line: Some(line),
+            } => write!(f, "{}:{}", path.display(), line),
+            ConfigOrigin::File { path, line: None } => write!(f, "{}", path.display()),
+            ConfigOrigin::Env(var) => write!(f, "environment variable {}", var),
+            ConfigOrigin::Cli => write!(f, "command line"),
+        }
+    }
+}
+
+/// A single problem found while loading configuration
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigIssue {
+    pub origin: ConfigOrigin,
+    /// Dotted key path (e.g. `poller.min_interval_secs`), if known
+    pub key: Option<String>,
+    pub message: String,
+}
+
+impl std::fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.key {
+            Some(key) => write!(f, "{}: {}: {}", self.origin, key, self.message),
+            None => write!(f, "{}: {}", self.origin, self.message),
+        }
+    }
+}
+
+/// Configuration could not be loaded or failed validation
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigError {
+    pub issues: Vec<ConfigIssue>,
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let lines: Vec<String> = self.issues.iter().map(|i| i.to_string()).collect();
+        write!(f, "Invalid configuration:\n{}", lines.join("\n"))
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl ConfigError {
+    /// Short single-line description suitable for a tooltip
+    pub fn summary(&self) -> String {
+        match self.issues.as_slice() {
+            [] => "Invalid configuration".to_string(),
+            [issue] => issue.to_string(),
+            [first, rest @ ..] => format!("{} (+{} more)", first, rest.len()),
+        }
+    }
+}
+
+/// Default config file location (`$XDG_CONFIG_HOME/usage-indicator/config.toml` on Linux)
+pub fn default_config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(APP_DIR_NAME).join(CONFIG_FILE_NAME))
+}
+
+/// Convert a byte offset into a 1-based line number
+fn line_of(source: &str, offset: usize) -> usize {
+    source[..offset.min(source.len())].matches('\n').count() + 1
+}
+
+/// Locate the line of a dotted key in the original TOML source
+fn find_key_line(source: &str, key: &str) -> Option<usize> {
+    let root = DeTable::parse(source).ok()?;
+    let mut table = root.get_ref();
+    let mut parts = key.split('.').peekable();
+
+    while let Some(part) = parts.next() {
+        let value = table.get(part)?;
+        if parts.peek().is_none() {
+            return Some(line_of(source, value.span().start));
+        }
+        table = match value.get_ref() {
+            DeValue::Table(inner) => inner,
+            _ => return None,
+        };
+    }
+    None
+}
+
+/// Parse an override value as TOML, falling back to a bare string
+fn parse_override_value(raw: &str) -> Value {
+    raw.parse::<Value>()
+        .unwrap_or_else(|_| Value::String(raw.to_string()))
+}
+
+/// Insert a value at a dotted key path, creating intermediate tables
+fn insert_dotted(table: &mut Table, key: &str, value: Value) {
+    let mut parts: Vec<&str> = key.split('.').collect();
+    let last = parts.pop().unwrap_or(key);
+
+    let mut current = table;
+    for part in parts {
+        let entry = current
+            .entry(part.to_string())
+            .or_insert_with(|| Value::Table(Table::new()));
+        if !entry.is_table() {
+            *entry = Value::Table(Table::new());
+        }
+        current = entry.as_table_mut().expect("entry was just made a table");
+    }
+    current.insert(last.to_string(), value);
+}
+
+/// Layered configuration loader: file < environment < command line
+#[derive(Clone, Default)]
+pub struct ConfigLoader {
+    path: Option<PathBuf>,
+    env: Vec<(String, String)>,
+    cli: Vec<(String, String)>,
+}
+
+impl ConfigLoader {
+    pub fn new(path: Option<PathBuf>) -> Self {
+        Self {
+            path,
+            ..Default::default()
+        }
+    }
+
+    /// Capture overrides from the process environment
+    pub fn with_process_env(self) -> Self {
+        self.with_env(ENV_OVERRIDES.iter().filter_map(|(var, _)| {
+            std::env::var(var)
+                .ok()
+                .map(|value| (var.to_string(), value))
+        }))
+    }
+
+    /// Add environment overrides as (variable, value) pairs
+    pub fn with_env(mut self, vars: impl IntoIterator<Item = (String, String)>) -> Self {
+        self.env.extend(vars);
+        self
+    }
+
+    /// Add command line overrides as (dotted key, value) pairs
+    pub fn with_cli(mut self, overrides: impl IntoIterator<Item = (String, String)>) -> Self {
+        self.cli.extend(overrides);
+        self
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Load, merge and validate the configuration
+    pub fn load(&self) -> Result<AppConfig, ConfigError> {
+        let source = match &self.path {
+            Some(path) => match std::fs::read_to_string(path) {
+                Ok(source) => Some(source),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+                Err(e) => {
+                    return Err(ConfigError {
+                        issues: vec![ConfigIssue {
+                            origin: ConfigOrigin::File {
+                                path: path.clone(),
+                                line: None,
+                            },
+                            key: None,
+                            message: format!("Failed to read config file: {}", e),
+                        }],
+                    });
+                }
+            },
+            None => None,
+        };
+        self.load_from_source(source.as_deref())
+    }
+
+    /// Load using the given file contents instead of reading `path`
+    pub fn load_from_source(&self, source: Option<&str>) -> Result<AppConfig, ConfigError> {
+        let file_path = self.path.clone().unwrap_or_default();
+        let file_origin = |line| ConfigOrigin::File {
+            path: file_path.clone(),
+            line,
+        };
+        let mut issues = Vec::new();
+
+        // Layer 1: config file, deserialized on its own so errors carry spans
+        let mut table = Table::new();
+        if let Some(source) = source {
+            if let Err(e) = toml::from_str::<AppConfig>(source) {
+                issues.push(ConfigIssue {
+                    origin: file_origin(e.span().map(|span| line_of(source, span.start))),
+                    key: None,
+                    message: e.message().to_string(),
+                });
+                return Err(ConfigError { issues });
+            }
+            table = toml::from_str(source).map_err(|e| ConfigError {
+                issues: vec![ConfigIssue {
+                    origin: file_origin(None),
+                    key: None,
+                    message: e.message().to_string(),
+                }],
+            })?;
+        }
+
+        // Layers 2 and 3: environment, then command line. Each override is
+        // checked individually so a bad value can be attributed to its source.
+        let mut origins: HashMap<String, ConfigOrigin> = HashMap::new();
+        let env_overrides = self.env.iter().filter_map(|(var, value)| {
+            ENV_OVERRIDES
+                .iter()
+                .find(|(name, _)| name == var)
+                .map(|(_, key)| (key.to_string(), value, ConfigOrigin::Env(var.clone())))
+        });
+        let cli_overrides = self
+            .cli
+            .iter()
+            .map(|(key, value)| (key.clone(), value, ConfigOrigin::Cli));
+
+        for (key, raw, origin) in env_overrides.chain(cli_overrides) {
+            // Values are parsed as TOML first, then retried as plain strings so
+            // that numeric-looking credentials still land in string fields
+            let mut attempts = vec![parse_override_value(raw)];
+            if !attempts[0].is_str() {
+                attempts.push(Value::String(raw.clone()));
+            }
+
+            let mut last_error = None;
+            for value in attempts {
+                let mut candidate = table.clone();
+                insert_dotted(&mut candidate, &key, value);
+                match candidate.clone().try_into::<AppConfig>() {
+                    Ok(_) => {
+                        table = candidate;
+                        last_error = None;
+                        break;
+                    }
+                    Err(e) => {
+                        last_error.get_or_insert(e);
+                    }
+                }
+            }
+
+            match last_error {
+                None => {
+                    origins.insert(key, origin);
+                }
+                Some(e) => issues.push(ConfigIssue {
+                    origin,
+                    key: Some(key),
+                    message: e.message().to_string(),
+                }),
+            }
+        }
+
+        if !issues.is_empty() {
+            return Err(ConfigError { issues });
+        }
+
+        let config: AppConfig = table.try_into().map_err(|e| ConfigError {
+            issues: vec![ConfigIssue {
+                origin: file_origin(None),
+                key: None,
+                message: e.message().to_string(),
+            }],
+        })?;
+
+        // Semantic validation, attributed to whichever layer set the key
+        for (key, message) in config.validate() {
+            let origin = origins.get(key).cloned().unwrap_or_else(|| {
+                file_origin(source.and_then(|source| find_key_line(source, key)))
+            });
+            issues.push(ConfigIssue {
+                origin,
+                key: Some(key.to_string()),
+                message,
+            });
+        }
+
+        if issues.is_empty() {
+            Ok(config)
+        } else {
+            Err(ConfigError { issues })
+        }
+    }
+}
+
+/// Watch the config file and emit a reload result whenever its contents change
+///
+/// The file is polled rather than watched with OS notifications, which keeps
+/// editors that save via rename-and-replace working without special casing.
+pub fn watch_config(
+    loader: ConfigLoader,
+    poll_interval: Duration,
+) -> mpsc::UnboundedReceiver<Result<AppConfig, ConfigError>> {
+    let (tx, rx) = mpsc::unbounded_channel();
+
+    let Some(path) = loader.path().map(Path::to_path_buf) else {
+        return rx;
+    };
+    let read = |path: &Path| std::fs::read_to_string(path).ok();
+    // Snapshot the current contents before returning so no edit is missed
+    let mut last_contents = read(&path);
+
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(poll_interval).await;
+
+            let contents = read(&path);
+            if contents == last_contents {
+                continue;
+            }
+            last_contents = contents;
+
+            debug!(path = %path.display(), "Config file changed, reloading");
+            if tx
+                .send(loader.load_from_source(last_contents.as_deref()))
+                .is_err()
+            {
+                // Receiver dropped, polling loop has stopped
+                break;
+            }
+        }
+    });
+
+    rx
+}
+
+impl AppConfig {
+    /// Check cross-field constraints, returning (key, message) pairs
+    pub fn validate(&self) -> Vec<(&'static str, String)> {
+        let mut errors = Vec::new();
+        let poller = &self.poller;
+        let retry = &self.retry;
+
+        if poller.min_interval_secs == 0 {
+            errors.push(("poller.min_interval_secs", "must be greater than 0".into()));
+        }
+        if poller.min_interval_secs > poller.max_interval_secs {
+            errors.push((
+                "poller.min_interval_secs",
+                format!(
+                    "must not exceed max_interval_secs ({} > {})",
+                    poller.min_interval_secs, poller.max_interval_secs
+                ),
+            ));
+        }
+        if poller.recency_window_secs > poller.context_window_secs {
+            errors.push((
+                "poller.recency_window_secs",
+                format!(
+                    "must not exceed context_window_secs ({} > {})",
+                    poller.recency_window_secs, poller.context_window_secs
+                ),
+            ));
+        }
+        for (key, value) in [
+            (
+                "poller.five_hour_sustained_threshold",
+                poller.five_hour_sustained_threshold,
+            ),
+            (
+                "poller.weekly_sustained_threshold",
+                poller.weekly_sustained_threshold,
+            ),
+            (
+                "poller.five_hour_recent_threshold",
+                poller.five_hour_recent_threshold,
+            ),
+        ] {
+            if !(0.1..=100.0).contains(&value) {
+                errors.push((key, format!("must be between 0.1 and 100 (got {})", value)));
+            }
+        }
+        for (key, value) in [
+            ("poller.warm_multiplier", poller.warm_multiplier),
+            ("poller.hot_multiplier", poller.hot_multiplier),
+            ("poller.blazing_multiplier", poller.blazing_multiplier),
+        ] {
+            if value.is_nan() || value <= 0.0 || value >= 1.0 {
+                errors.push((key, format!("must be between 0 and 1 (got {})", value)));
+            }
+        }
+        if poller.fixed_interval_secs == 0 {
+            errors.push((
+                "poller.fixed_interval_secs",
+                "must be greater than 0".into(),
+            ));
+        }
+        if poller.idle_doubling_secs == 0 {
+            errors.push(("poller.idle_doubling_secs", "must be greater than 0".into()));
+        }
+        if poller.daily_requests == 0 {
+            errors.push(("poller.daily_requests", "must be greater than 0".into()));
+        }
+
+        if retry.min_delay_secs == 0 {
+            errors.push(("retry.min_delay_secs", "must be greater than 0".into()));
+        }
+        if retry.min_delay_secs > retry.max_delay_secs {
+            errors.push((
+                "retry.min_delay_secs",
+                format!(
+                    "must not exceed max_delay_secs ({} > {})",
+                    retry.min_delay_secs, retry.max_delay_secs
+                ),
+            ));
+        }
+        if retry.multiplier.is_nan() || retry.multiplier < 1.0 {
+            errors.push((
+                "retry.multiplier",
+                format!("must be at least 1 (got {})", retry.multiplier),
+            ));
+        }
+        if self.breaker.failure_threshold == 0 {
+            errors.push(("breaker.failure_threshold", "must be greater than 0".into()));
+        }
+        if self.breaker.probe_interval_secs == 0 {
+            errors.push((
+                "breaker.probe_interval_secs",
+                "must be greater than 0".into(),
+            ));
+        }
+
+        if self.icon.staleness_threshold_secs == 0 {
+            errors.push((
+                "icon.staleness_threshold_secs",
+                "must be greater than 0".into(),
+            ));
+        }
+        // Usage is tracked to a tenth of a percent
+        if self.icon.decimals > 1 {
+            errors.push((
+                "icon.decimals",
+                format!("must be 0 or 1 (got {})", self.icon.decimals),
+            ));
+        }
+        if self.icon.metric.as_deref().is_some_and(str::is_empty) {
+            errors.push(("icon.metric", "must not be empty".into()));
+        }
+
+        if self.history.retention_days == 0 {
+            errors.push(("history.retention_days", "must be greater than 0".into()));
+        }
+
+        let notifications = &self.notifications;
+        if let Some(&threshold) = notifications
+            .thresholds
+            .iter()
+            .find(|&&t| t == 0 || t > 100)
+        {
+            errors.push((
+                "notifications.thresholds",
+                format!("must be between 1 and 100 (got {})", threshold),
+            ));
+        }
+        if !(notifications.hysteresis >= 0.0 && notifications.hysteresis < 100.0) {
+            errors.push((
+                "notifications.hysteresis",
+                format!(
+                    "must be between 0 and 100 (got {})",
+                    notifications.hysteresis
+                ),
+            ));
+        }
+        if notifications.auth_failure_threshold == 0 {
+            errors.push((
+                "notifications.auth_failure_threshold",
+                "must be greater than 0".into(),
+            ));
+        }
+
+        if self.http.connect_timeout_secs == 0 {
+            errors.push(("http.connect_timeout_secs", "must be greater than 0".into()));
+        }
+        if self.http.timeout_secs == 0 {
+            errors.push(("http.timeout_secs", "must be greater than 0".into()));
+        }
+
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::StrategyKind;
+    use assert2::{assert, let_assert};
+
+    fn loader() -> ConfigLoader {
+        ConfigLoader::new(Some(PathBuf::from("config.toml")))
+    }
+
+    #[test]
+    fn test_missing_file_uses_defaults() {
+        let_assert!(Ok(config) = loader().load_from_source(None));
+        assert!(config == AppConfig::default());
+    }
+
+    #[test]
+    fn test_file_values_are_applied() {
+        let source = r#"
+[poller]
+min_interval_secs = 60
+warm_multiplier = 0.5
+
+[retry]
+max_delay_secs = 600
+
+[icon]
+metric = "five_hour"
+
+[credentials]
+org_id = "org-from-file"
+"#;
+        let_assert!(Ok(config) = loader().load_from_source(Some(source)));
+        assert!(config.poller.min_interval_secs == 60);
+        assert!(config.poller.warm_multiplier == 0.5);
+        assert!(config.poller.max_interval_secs == PollerConfig::default().max_interval_secs);
+        assert!(config.retry.max_delay_secs == 600);
+        assert!(config.icon.metric.as_deref() == Some("five_hour"));
+        assert!(config.credentials.org_id.as_deref() == Some("org-from-file"));
+    }
+
+    #[test]
+    fn test_layering_file_env_cli() {
+        let source = "[poller]\nmin_interval_secs = 60\nmax_interval_secs = 600\n";
+        let_assert!(
+            Ok(config) = loader()
+                .with_env([
+                    ("POLL_MIN_INTERVAL_SECS".to_string(), "90".to_string()),
+                    ("POLL_MAX_INTERVAL_SECS".to_string(), "900".to_string()),
+                    (
+                        "CLAUDE_SESSION_KEY".to_string(),
+                        "sk-ant-secret".to_string()
+                    ),
+                ])
+                .with_cli([("poller.min_interval_secs".to_string(), "120".to_string())])
+                .load_from_source(Some(source))
+        );
+
+        assert!(config.poller.min_interval_secs == 120);
+        assert!(config.poller.max_interval_secs == 900);
+        assert!(config.credentials.session_key.as_deref() == Some("sk-ant-secret"));
+    }
+
+    #[test]
+    fn test_strategy_from_file_and_env() {
+        let source = "[poller]\nstrategy = \"daily_budget\"\ndaily_requests = 48\n";
+        let_assert!(Ok(config) = loader().load_from_source(Some(source)));
+        assert!(config.poller.strategy == StrategyKind::DailyBudget);
+        assert!(config.poller.daily_requests == 48);
+
+        let_assert!(
+            Ok(config) = loader()
+                .with_env([("POLL_STRATEGY".to_string(), "fixed".to_string())])
+                .load_from_source(Some(source))
+        );
+        assert!(config.poller.strategy == StrategyKind::Fixed);
+
+        let source = "[poller]\nstrategy = \"sometimes\"\n";
+        let_assert!(Err(error) = loader().load_from_source(Some(source)));
+        assert!(
+            error.issues[0].origin
+                == ConfigOrigin::File {
+                    path: PathBuf::from("config.toml"),
+                    line: Some(2),
+                }
+        );
+    }
+
+    #[test]
+    fn test_validation_reports_file_line() {
+        let source = "[poller]\nmax_interval_secs = 100\nmin_interval_secs = 500\n";
+        let_assert!(Err(error) = loader().load_from_source(Some(source)));
+
+        assert!(error.issues.len() == 1);
+        let issue = &error.issues[0];
+        assert!(issue.key.as_deref() == Some("poller.min_interval_secs"));
+        assert!(
+            issue.origin
+                == ConfigOrigin::File {
+                    path: PathBuf::from("config.toml"),
+                    line: Some(3),
+                }
+        );
+        assert!(
+            issue
+                .to_string()
+                .starts_with("config.toml:3: poller.min_interval_secs")
+        );
+    }
+
+    #[test]
+    fn test_validation_reports_multiplier_out_of_range() {
+        let source = "[poller]\n\nhot_multiplier = 1.5\nblazing_multiplier = 0.0\n";
+        let_assert!(Err(error) = loader().load_from_source(Some(source)));
+
+        let keys: Vec<_> = error
+            .issues
+            .iter()
+            .map(|i| (i.key.as_deref().unwrap(), i.origin.clone()))
+            .collect();
+        assert!(keys.len() == 2);
+        assert!(keys.contains(&(
+            "poller.hot_multiplier",
+            ConfigOrigin::File {
+                path: PathBuf::from("config.toml"),
+                line: Some(3),
+            }
+        )));
+        assert!(keys.contains(&(
+            "poller.blazing_multiplier",
+            ConfigOrigin::File {
+                path: PathBuf::from("config.toml"),
+                line: Some(4),
+            }
+        )));
+    }
+
+    #[test]
+    fn test_validation_rejects_zero_retry_delay() {
+        let source = "[retry]\nmin_delay_secs = 0\n";
+        let_assert!(Err(error) = loader().load_from_source(Some(source)));
+
+        assert!(error.issues.len() == 1);
+        assert!(error.issues[0].key.as_deref() == Some("retry.min_delay_secs"));
+        assert!(error.issues[0].message == "must be greater than 0");
+    }
+
+    #[test]
+    fn test_type_error_reports_file_line() {
+        let source = "[retry]\nmultiplier = 2.0\nmin_delay_secs = \"five\"\n";
+        let_assert!(Err(error) = loader().load_from_source(Some(source)));
+
+        assert!(
+            error.issues[0].origin
+                == ConfigOrigin::File {
+                    path: PathBuf::from("config.toml"),
+                    line: Some(3),
+                }
+        );
+    }
+
+    #[test]
+    fn test_unknown_key_is_rejected() {
+        let source = "[poller]\nmin_interval = 60\n";
+        let_assert!(Err(error) = loader().load_from_source(Some(source)));
+        assert!(error.issues[0].message.contains("min_interval"));
+    }
+
+    #[test]
+    fn test_numeric_env_value_in_string_field() {
+        let_assert!(
+            Ok(config) = loader()
+                .with_env([("CLAUDE_ORG_ID".to_string(), "12345".to_string())])
+                .load_from_source(None)
+        );
+        assert!(config.credentials.org_id.as_deref() == Some("12345"));
+    }
+
+    #[test]
+    fn test_array_env_value() {
+        let_assert!(
+            Ok(config) = loader()
+                .with_env([("NOTIFY_THRESHOLDS".to_string(), "[50, 75]".to_string())])
+                .load_from_source(None)
+        );
+        assert!(config.notifications.thresholds == vec![50, 75]);
+    }
+
+    #[test]
+    fn test_invalid_env_override_is_attributed() {
+        let_assert!(
+            Err(error) = loader()
+                .with_env([("RETRY_MULTIPLIER".to_string(), "fast".to_string())])
+                .load_from_source(None)
+        );
+
+        assert!(error.issues.len() == 1);
+        assert!(error.issues[0].origin == ConfigOrigin::Env("RETRY_MULTIPLIER".to_string()));
+        assert!(error.issues[0].key.as_deref() == Some("retry.multiplier"));
+    }
+
+    #[test]
+    fn test_cli_validation_error_is_attributed() {
+        let_assert!(
+            Err(error) = loader()
+                .with_cli([("retry.min_delay_secs".to_string(), "1000".to_string())])
+                .load_from_source(None)
+        );
+
+        assert!(error.issues[0].origin == ConfigOrigin::Cli);
+    }
+
+    #[test]
+    fn test_error_summary_is_single_line() {
+        let source = "[poller]\nhot_multiplier = 1.5\nblazing_multiplier = 0.0\n";
+        let_assert!(Err(error) = loader().load_from_source(Some(source)));
+
+        let summary = error.summary();
+        assert!(!summary.contains('\n'));
+        assert!(summary.ends_with("(+1 more)"));
+    }
+
+    #[tokio::test]
+    async fn test_watch_config_emits_on_change() {
+        let path =
+            std::env::temp_dir().join(format!("usage-indicator-watch-{}.toml", std::process::id()));
+        std::fs::write(&path, "[poller]\nmin_interval_secs = 60\n").unwrap();
+
+        let mut rx = watch_config(
+            ConfigLoader::new(Some(path.clone())),
+            Duration::from_millis(10),
+        );
+
+        std::fs::write(&path, "[poller]\nmin_interval_secs = 90\n").unwrap();
+        let_assert!(Some(Ok(config)) = rx.recv().await);
+        assert!(config.poller.min_interval_secs == 90);
+
+        std::fs::write(&path, "[poller]\nmin_interval_secs = 9000\n").unwrap();
+        let_assert!(Some(Err(error)) = rx.recv().await);
+        assert!(error.issues[0].key.as_deref() == Some("poller.min_interval_secs"));
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_credentials_debug_redacts_session_key() {
+        let credentials = CredentialsConfig {
+            org_id: Some("org".to_string()),
+            session_key: Some("sk-ant-secret".to_string()),
+        };
+        let debug = format!("{:?}", credentials);
+        assert!(!debug.contains("sk-ant-secret"));
+        assert!(debug.contains("<redacted>"));
+    }
+}
@@ -0,0 +1,192 @@
+use crate::error::FetchError;
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Serializer};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Fetch attempts kept for the diagnostics view
+pub const MAX_ATTEMPTS: usize = 50;
+
+/// One fetch, successful or not
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FetchAttempt {
+    pub started_at: DateTime<Utc>,
+    #[serde(rename = "duration_ms", serialize_with = "as_millis")]
+    pub duration: Duration,
+    /// Status of the HTTP response, when there was one
+    pub http_status: Option<u16>,
+    /// Short error name (e.g. "auth", "timeout"), `None` on success
+    pub error_kind: Option<String>,
+    pub error: Option<String>,
+    /// Wait chosen before retrying, `None` on success
+    #[serde(rename = "retry_delay_ms", serialize_with = "as_millis_opt")]
+    pub retry_delay: Option<Duration>,
+}
+
+impl FetchAttempt {
+    pub fn success(
+        started_at: DateTime<Utc>,
+        duration: Duration,
+        http_status: Option<u16>,
+    ) -> Self {
+        Self {
+            started_at,
+            duration,
+            http_status,
+            error_kind: None,
+            error: None,
+            retry_delay: None,
+        }
+    }
+
+    pub fn failure(
+        started_at: DateTime<Utc>,
+        duration: Duration,
+        error: &FetchError,
+        retry_delay: Duration,
+    ) -> Self {
+        Self {
+            started_at,
+            duration,
+            http_status: error.http_status(),
+            error_kind: Some(error.kind_name().into_owned()),
+            error: Some(error.to_string()),
+            retry_delay: Some(retry_delay),
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.error_kind.is_none()
+    }
+}
+
+fn as_millis<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(duration.as_millis() as u64)
+}
+
+fn as_millis_opt<S: Serializer>(
+    duration: &Option<Duration>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match duration {
+        Some(duration) => as_millis(duration, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// The most recent fetch attempts, oldest first
+///
+/// `AppState::current_error` only holds the latest failure; this keeps enough
+/// history to explain an error that has since cleared.
+#[derive(Debug, Clone)]
+pub struct AttemptLog {
+    attempts: VecDeque<FetchAttempt>,
+    capacity: usize,
+}
+
+impl Default for AttemptLog {
+    fn default() -> Self {
+        Self::with_capacity(MAX_ATTEMPTS)
+    }
+}
+
+impl AttemptLog {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            attempts: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Add an attempt, dropping the oldest once full
+    pub fn record(&mut self, attempt: FetchAttempt) {
+        if self.attempts.len() == self.capacity {
+            self.attempts.pop_front();
+        }
+        self.attempts.push_back(attempt);
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &FetchAttempt> {
+        self.attempts.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.attempts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.attempts.is_empty()
+    }
+
+    /// All attempts as a pretty-printed JSON array
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(&self.attempts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::NetworkErrorKind;
+    use assert2::assert;
+    use chrono::Duration as ChronoDuration;
+
+    fn started_at(minute: i64) -> DateTime<Utc> {
+        "2025-11-22T12:00:00Z".parse::<DateTime<Utc>>().unwrap() + ChronoDuration::minutes(minute)
+    }
+
+    #[test]
+    fn test_log_keeps_most_recent_attempts() {
+        let mut log = AttemptLog::with_capacity(3);
+        for minute in 0..5 {
+            log.record(FetchAttempt::success(
+                started_at(minute),
+                Duration::from_millis(200),
+                Some(200),
+            ));
+        }
+
+        assert!(log.len() == 3);
+        let kept: Vec<_> = log.iter().map(|attempt| attempt.started_at).collect();
+        assert!(kept == [started_at(2), started_at(3), started_at(4)]);
+    }
+
+    #[test]
+    fn test_attempts_export_as_json() {
+        let mut log = AttemptLog::default();
+        log.record(FetchAttempt::success(
+            started_at(0),
+            Duration::from_millis(812),
+            Some(200),
+        ));
+        let error = FetchError::network(NetworkErrorKind::HttpStatus(503), "HTTP 503");
+        log.record(FetchAttempt::failure(
+            started_at(3),
+            Duration::from_millis(95),
+            &error,
+            Duration::from_secs(10),
+        ));
+
+        let json: serde_json::Value = serde_json::from_str(&log.to_json().unwrap()).unwrap();
+        assert!(
+            json == serde_json::json!([
+                {
+                    "started_at": "2025-11-22T12:00:00Z",
+                    "duration_ms": 812,
+                    "http_status": 200,
+                    "error_kind": null,
+                    "error": null,
+                    "retry_delay_ms": null
+                },
+                {
+                    "started_at": "2025-11-22T12:03:00Z",
+                    "duration_ms": 95,
+                    "http_status": 503,
+                    "error_kind": "http_503",
+                    "error": "Network error: HTTP 503",
+                    "retry_delay_ms": 10000
+                }
+            ])
+        );
+    }
+}
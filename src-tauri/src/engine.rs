@@ -0,0 +1,518 @@
+use crate::breaker::{BreakerState, CircuitBreaker};
+use crate::commands::{PollCommand, PollSchedule};
+use crate::config::{AppConfig, ConfigError, ConfigLoader};
+use crate::diagnostics::FetchAttempt;
+use crate::error::FetchError;
+use crate::events::SystemEvent;
+use crate::forecast::{LONG_WINDOW_LOOKBACK, forecast_snapshot, history_rate};
+use crate::history::{FetchRecord, HistoryStore};
+use crate::notify::{Notification, NotificationTracker};
+use crate::poller::AdaptivePoller;
+use crate::provider::{UsageProvider, UsageSnapshot};
+use crate::retry::RetryState;
+use crate::state::{AppState, UserPause};
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime};
+use tokio::time::Instant;
+use tracing::{error, info, warn};
+
+/// Source of time for the polling engine
+pub trait Clock: Send + Sync + 'static {
+    /// Monotonic time used for scheduling
+    fn now(&self) -> Instant;
+
+    /// Wall-clock time used for timestamps and reset countdowns
+    fn wall_now(&self) -> DateTime<Utc>;
+}
+
+/// The real clock
+///
+/// Uses tokio's `Instant`, so scheduling follows `tokio::time::pause()`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn wall_now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Something that happened outside the engine
+#[derive(Debug, Clone)]
+pub enum EngineInput {
+    Command(PollCommand),
+    System(SystemEvent),
+    ConfigReloaded(Box<Result<AppConfig, ConfigError>>),
+}
+
+/// Side effects the driver must carry out after each step
+#[derive(Debug, Clone, PartialEq)]
+pub enum Effect {
+    /// Re-render the tray from the engine's current state
+    Render,
+
+    /// Show a desktop notification
+    Notify(Notification),
+
+    /// Call `wake` at this instant, or only wait for input when `None`
+    ///
+    /// Always the last effect of a step.
+    SleepUntil(Option<Instant>),
+}
+
+/// The polling loop as a state machine
+///
+/// The engine owns the poller, retry, circuit breaker and app state but
+/// performs no I/O other than the provider fetch and the optional history
+/// store. A driver feeds it inputs, calls `wake` when the requested deadline
+/// passes and carries out the returned effects, which lets tests drive it
+/// on a clock they advance by hand.
+pub struct PollingEngine<P: UsageProvider, C: Clock> {
+    provider: P,
+    clock: C,
+    config: AppConfig,
+    loader: Option<ConfigLoader>,
+    poller: AdaptivePoller,
+    retry_state: RetryState,
+    breaker: CircuitBreaker,
+    state: AppState,
+    schedule: PollSchedule,
+    notifications: NotificationTracker,
+    history: Option<HistoryStore>,
+}
+
+impl<P: UsageProvider, C: Clock> PollingEngine<P, C> {
+    /// Create an engine that fetches immediately on the first `wake`
+    pub fn new(provider: P, clock: C, config: AppConfig) -> Self {
+        info!(
+            provider = provider.id(),
+            poller = ?config.poller,
+            retry = ?config.retry,
+            breaker = ?config.breaker,
+            "Polling {} usage",
+            provider.display_name()
+        );
+
+        Self {
+            poller: AdaptivePoller::new(config.poller.clone()),
+            retry_state: RetryState::new(config.retry.clone()),
+            breaker: CircuitBreaker::new(config.breaker.clone()),
+            state: AppState::new(),
+            schedule: PollSchedule::new(clock.now()),
+            notifications: NotificationTracker::new(config.notifications.clone()),
+            history: None,
+            loader: None,
+            provider,
+            clock,
+            config,
+        }
+    }
+
+    /// Loader used by the `ReloadConfig` command
+    pub fn with_loader(mut self, loader: ConfigLoader) -> Self {
+        self.loader = Some(loader);
+        self
+    }
+
+    /// Seed the retry and polling jitter so delays are reproducible
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.retry_state.reseed(seed);
+        self.poller.reseed(seed.wrapping_add(1));
+        self
+    }
+
+    /// Show `warning` until a valid config is loaded, e.g. when the file was invalid at startup
+    pub fn with_config_warning(mut self, warning: String) -> Self {
+        self.state.config_warning = Some(warning);
+        self
+    }
+
+    /// Record fetches to `store` and seed the poller from its recent history
+    pub fn with_history(mut self, mut store: HistoryStore) -> Self {
+        self.restore_poller(&mut store);
+        self.history = Some(store);
+        self
+    }
+
+    pub fn provider(&self) -> &P {
+        &self.provider
+    }
+
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+
+    pub fn config(&self) -> &AppConfig {
+        &self.config
+    }
+
+    pub fn state(&self) -> &AppState {
+        &self.state
+    }
+
+    pub fn poller(&self) -> &AdaptivePoller {
+        &self.poller
+    }
+
+    pub fn retry_state(&self) -> &RetryState {
+        &self.retry_state
+    }
+
+    pub fn breaker(&self) -> &CircuitBreaker {
+        &self.breaker
+    }
+
+    /// When `wake` should next be called (None while paused indefinitely)
+    pub fn next_wake(&self) -> Option<Instant> {
+        let fetch_at = self
+            .schedule
+            .fetch_enabled()
+            .then(|| self.schedule.next_fetch_at());
+        match (fetch_at, self.schedule.pause_expires_at()) {
+            (Some(fetch_at), Some(expires_at)) => Some(fetch_at.min(expires_at)),
+            (fetch_at, expires_at) => fetch_at.or(expires_at),
+        }
+    }
+
+    fn finish(&self, mut effects: Vec<Effect>) -> Vec<Effect> {
+        effects.push(Effect::SleepUntil(self.next_wake()));
+        effects
+    }
+
+    /// Process an input without fetching
+    pub fn handle(&mut self, input: EngineInput) -> Vec<Effect> {
+        let now = self.clock.now();
+        let mut effects = Vec::new();
+
+        match input {
+            EngineInput::System(event) => {
+                let action = event.recommended_action();
+                info!(?event, ?action, "System event received");
+                self.schedule.apply_system_action(action, now);
+            }
+            EngineInput::Command(command) => {
+                info!(?command, "Command received");
+                self.schedule.apply_command(command, now);
+
+                match command {
+                    PollCommand::Pause(duration) => {
+                        self.state.user_pause = Some(match duration {
+                            Some(d) => UserPause::Until(self.wall_time() + d),
+                            None => UserPause::Indefinite,
+                        });
+                    }
+                    PollCommand::Resume => self.state.user_pause = None,
+                    PollCommand::ResetBackoff => {
+                        let was_tripped = self.breaker.reset();
+                        if self.retry_state.reset() || was_tripped {
+                            info!("Backoff reset, retrying now");
+                            self.schedule.fetch_now(now);
+                        }
+                    }
+                    PollCommand::ReloadConfig => match self.loader.as_ref().map(|l| l.load()) {
+                        Some(reload) => self.apply_reload(reload),
+                        None => warn!("No config loader available, ignoring reload"),
+                    },
+                    PollCommand::RefreshNow => {}
+                }
+                effects.push(Effect::Render);
+            }
+            EngineInput::ConfigReloaded(reload) => {
+                self.apply_reload(*reload);
+                effects.push(Effect::Render);
+            }
+        }
+
+        self.finish(effects)
+    }
+
+    /// Handle a passed deadline: end an expired pause and fetch if due
+    pub async fn wake(&mut self) -> Vec<Effect> {
+        let now = self.clock.now();
+        let mut effects = Vec::new();
+
+        if self
+            .schedule
+            .pause_expires_at()
+            .is_some_and(|expires_at| expires_at <= now)
+        {
+            info!("Pause expired, resuming polling");
+            self.schedule.resume(now);
+            self.state.user_pause = None;
+            effects.push(Effect::Render);
+        }
+
+        if self.schedule.fetch_enabled() && self.schedule.next_fetch_at() <= now {
+            self.fetch(now, &mut effects).await;
+        }
+
+        self.finish(effects)
+    }
+
+    fn wall_time(&self) -> SystemTime {
+        self.clock.wall_now().into()
+    }
+
+    async fn fetch(&mut self, now: Instant, effects: &mut Vec<Effect>) {
+        self.schedule.fetch_started();
+        self.breaker.fetch_started();
+        info!("Fetching usage data...");
+        let started_at = self.clock.wall_now();
+
+        let result = self.provider.fetch().await.and_then(|snapshot| {
+            let metrics = snapshot.usage_metrics(self.provider.metrics())?;
+            Ok((snapshot, metrics))
+        });
+
+        let duration = self.clock.now().saturating_duration_since(now);
+
+        let delay = match result {
+            Ok((snapshot, metrics)) => {
+                self.state.attempts.record(FetchAttempt::success(
+                    started_at,
+                    duration,
+                    snapshot.http_status,
+                ));
+                info!(
+                    five_hour_pct = metrics.five_hour().as_pct(),
+                    weekly_pct = metrics.weekly().as_pct(),
+                    "Usage data fetched"
+                );
+
+                // Calculate next interval using adaptive algorithm
+                let next_interval = self.poller.next_interval(metrics.clone(), now.into_std());
+                let fetched_at = self.clock.wall_now();
+                // Don't sleep through a reset and keep showing pre-reset usage
+                let delay = self
+                    .poller
+                    .clamp_to_reset(next_interval, &metrics, fetched_at);
+
+                if let Some(store) = self.history.as_mut() {
+                    let record = FetchRecord {
+                        timestamp: fetched_at,
+                        periods: snapshot.periods.clone(),
+                        poller_state: self.poller.current_state(),
+                        interval: next_interval,
+                        transitions: self.poller.last_transitions().to_vec(),
+                    };
+                    if let Err(e) = store.record(&record) {
+                        warn!("Failed to record usage history: {}", e);
+                    }
+                }
+
+                let long_term = self
+                    .history
+                    .as_ref()
+                    .map(|store| long_term_rates(store, fetched_at))
+                    .unwrap_or_default();
+                let forecasts = forecast_snapshot(
+                    self.provider.metrics(),
+                    &snapshot,
+                    &self.poller.momentum(now.into_std()),
+                    &long_term,
+                    fetched_at,
+                );
+                for forecast in forecasts.iter().filter(|f| f.exceeds_before_reset()) {
+                    info!(
+                        metric = %forecast.metric_id,
+                        rate_per_hour = forecast.rate_per_hour,
+                        "Projected to hit the limit before the window resets"
+                    );
+                }
+
+                let notifications = self.notifications.on_success(
+                    self.provider.display_name(),
+                    self.provider.metrics(),
+                    &snapshot,
+                    self.poller.last_transitions(),
+                );
+                self.push_notifications(notifications, effects);
+
+                // Update state with fresh data
+                self.state
+                    .update_success(metrics, snapshot, fetched_at.into());
+                self.state.forecasts = forecasts;
+                self.retry_state.record_success();
+                if self.breaker.record_success() {
+                    info!("Circuit breaker closed after a successful probe");
+                }
+
+                info!(
+                    state = ?self.poller.current_state(),
+                    next_interval_secs = next_interval.as_secs(),
+                    next_interval_mins = next_interval.as_secs() / 60,
+                    next_fetch_secs = delay.as_secs(),
+                    "Adaptive polling cycle complete"
+                );
+
+                delay
+            }
+            Err(e) => {
+                error!("Failed to fetch usage data: {}", e);
+
+                let notification = self
+                    .notifications
+                    .on_error(self.provider.display_name(), &e);
+                self.push_notifications(notification.into_iter().collect(), effects);
+
+                // Calculate retry delay with exponential backoff, stretched to fit
+                // a request budget and while the circuit breaker is open
+                let retry_delay = self.retry_state.record_failure(&e);
+                let retry_delay = self.poller.retry_delay(retry_delay, now.into_std());
+                let was_open = self.breaker.state() == BreakerState::Open;
+                let retry_delay = self.breaker.record_failure(&e, retry_delay);
+                if !was_open && self.breaker.state() == BreakerState::Open {
+                    warn!(
+                        probe_interval_secs = retry_delay.as_secs(),
+                        "Circuit breaker opened after repeated permanent failures"
+                    );
+                }
+
+                // Update state with error (keeps last-known-good data)
+                self.state.attempts.record(FetchAttempt::failure(
+                    started_at,
+                    duration,
+                    &e,
+                    retry_delay,
+                ));
+                self.state.update_error(e.clone());
+
+                // Retrying can't fix missing settings; wait for the user to edit them
+                if let FetchError::Config { key, .. } = &e {
+                    warn!(key = %key, "Polling stopped until the configuration changes");
+                    self.schedule.await_config();
+                }
+
+                info!(
+                    error_category = e.category(),
+                    error_kind = %e.kind_name(),
+                    is_transient = e.is_transient(),
+                    breaker = self.breaker.state().as_str(),
+                    retry_delay_secs = retry_delay.as_secs(),
+                    "Retrying after error"
+                );
+
+                retry_delay
+            }
+        };
+
+        effects.push(Effect::Render);
+        self.schedule.fetch_finished(delay, self.clock.now());
+    }
+
+    fn push_notifications(&self, notifications: Vec<Notification>, effects: &mut Vec<Effect>) {
+        if self.config.notifications.enabled {
+            effects.extend(notifications.into_iter().map(Effect::Notify));
+        }
+    }
+
+    /// Apply a reloaded configuration, keeping the previous one on error
+    fn apply_reload(&mut self, reload: Result<AppConfig, ConfigError>) {
+        match reload {
+            Ok(new_config) if new_config == self.config => {
+                self.state.config_warning = None;
+            }
+            Ok(new_config) => {
+                info!(
+                    poller = ?new_config.poller,
+                    retry = ?new_config.retry,
+                    icon = ?new_config.icon,
+                    "Configuration reloaded"
+                );
+                if new_config.notifications.enabled != self.config.notifications.enabled {
+                    warn!("Enabling or disabling notifications takes effect after a restart");
+                }
+
+                // Rebuild thresholds in place, keeping tracker history and state
+                self.provider.reconfigure(&new_config);
+                self.poller.update_config(new_config.poller.clone());
+                self.retry_state.update_config(new_config.retry.clone());
+                self.breaker.update_config(new_config.breaker.clone());
+                self.notifications
+                    .update_config(new_config.notifications.clone());
+                self.state.config_warning = None;
+                self.config = new_config;
+                let now = self.clock.now();
+                self.schedule.config_changed(now);
+                // New credentials or settings may fix what kept failing
+                if self.breaker.reset() {
+                    info!("Circuit breaker closed by config change, retrying now");
+                    self.schedule.retry_now(now);
+                }
+            }
+            Err(e) => {
+                warn!(
+                    "Config reload failed, keeping previous configuration: {}",
+                    e
+                );
+                self.state.config_warning = Some(e.summary());
+            }
+        }
+    }
+
+    /// Seed the poller from recent history so its state survives restarts
+    fn restore_poller(&mut self, store: &mut HistoryStore) {
+        let wall_now = self.clock.wall_now();
+        if let Err(e) = store.prune(wall_now) {
+            warn!("Failed to prune usage history: {}", e);
+        }
+
+        let records = match store.recent(self.poller.history_window(), wall_now) {
+            Ok(records) => records,
+            Err(e) => {
+                warn!("Failed to read usage history: {}", e);
+                return;
+            }
+        };
+        if records.is_empty() {
+            return;
+        }
+
+        let last_interval = records.last().map(|record| record.interval);
+        let descriptors = self.provider.metrics();
+        let samples = records.into_iter().filter_map(|record| {
+            let age: Duration = (wall_now - record.timestamp).to_std().unwrap_or_default();
+            let snapshot = UsageSnapshot {
+                periods: record.periods,
+                http_status: None,
+            };
+            // Skip records from before a provider changed its metrics
+            let metrics = snapshot.usage_metrics(descriptors).ok()?;
+            Some((age, metrics))
+        });
+
+        self.poller
+            .restore(samples, last_interval, self.clock.now().into_std());
+    }
+}
+
+/// Per-metric pace (points per hour) over the long-term lookback
+fn long_term_rates(store: &HistoryStore, now: DateTime<Utc>) -> BTreeMap<String, f64> {
+    let records = match store.recent(LONG_WINDOW_LOOKBACK, now) {
+        Ok(records) => records,
+        Err(e) => {
+            warn!("Failed to read usage history: {}", e);
+            return BTreeMap::new();
+        }
+    };
+
+    let mut samples: BTreeMap<String, Vec<(DateTime<Utc>, f64)>> = BTreeMap::new();
+    for record in records {
+        for (metric_id, period) in record.periods {
+            samples
+                .entry(metric_id)
+                .or_default()
+                .push((record.timestamp, period.utilization));
+        }
+    }
+
+    samples
+        .into_iter()
+        .filter_map(|(metric_id, samples)| Some((metric_id, history_rate(&samples)?)))
+        .collect()
+}
@@ -1,10 +1,146 @@
+use crate::config::env_var_for;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::sync::Arc;
+
+/// What went wrong with a request that didn't produce usable data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetworkErrorKind {
+    /// Connecting or the whole request took too long
+    Timeout,
+    /// Nothing was listening at the server's address
+    ConnectionRefused,
+    /// The server's name couldn't be resolved
+    Dns,
+    /// TLS handshake or certificate verification failed
+    Tls,
+    /// Any other failure to connect, including resets
+    Connect,
+    /// The server answered with an unexpected status
+    HttpStatus(u16),
+    /// The connection broke while reading the response
+    BodyRead,
+    /// The request couldn't be built (client setup, header values)
+    InvalidRequest,
+}
+
+impl NetworkErrorKind {
+    /// Short name for logs (e.g. "timeout", "http_503")
+    pub fn as_str(&self) -> Cow<'static, str> {
+        match self {
+            Self::Timeout => "timeout".into(),
+            Self::ConnectionRefused => "connection_refused".into(),
+            Self::Dns => "dns".into(),
+            Self::Tls => "tls".into(),
+            Self::Connect => "connect".into(),
+            Self::HttpStatus(code) => format!("http_{}", code).into(),
+            Self::BodyRead => "body_read".into(),
+            Self::InvalidRequest => "invalid_request".into(),
+        }
+    }
+
+    /// Classify a request error from its flags and source chain
+    fn classify(error: &wreq::Error) -> Self {
+        if error.is_timeout() {
+            return Self::Timeout;
+        }
+        if error.is_builder() {
+            return Self::InvalidRequest;
+        }
+        if error.is_body() || error.is_decode() {
+            return Self::BodyRead;
+        }
+
+        // A connection dropped mid-handshake also carries a TLS error, so the
+        // socket's own failure wins
+        for io in causes(error).filter_map(|cause| cause.downcast_ref::<std::io::Error>()) {
+            match io.kind() {
+                std::io::ErrorKind::TimedOut => return Self::Timeout,
+                std::io::ErrorKind::ConnectionRefused => return Self::ConnectionRefused,
+                std::io::ErrorKind::ConnectionReset
+                | std::io::ErrorKind::ConnectionAborted
+                | std::io::ErrorKind::UnexpectedEof => return Self::Connect,
+                _ => {}
+            }
+        }
+        if causes(error).any(|cause| {
+            cause.is::<boring2::ssl::Error>() || cause.is::<boring2::error::ErrorStack>()
+        }) {
+            return Self::Tls;
+        }
+        // The connector's error type is private, so its label is all there is
+        if error.is_connect()
+            && causes(error).any(|cause| cause.to_string().to_lowercase().starts_with("dns error"))
+        {
+            return Self::Dns;
+        }
+        Self::Connect
+    }
+}
+
+/// The chain of errors behind `error`
+///
+/// `io::Error::source` skips the error it wraps, so this steps into it instead.
+fn causes<'a>(
+    error: &'a (dyn std::error::Error + 'static),
+) -> impl Iterator<Item = &'a (dyn std::error::Error + 'static)> {
+    std::iter::successors(error.source(), |cause| {
+        match cause
+            .downcast_ref::<std::io::Error>()
+            .and_then(|io| io.get_ref())
+        {
+            Some(inner) => Some(inner),
+            None => cause.source(),
+        }
+    })
+}
+
+/// A failed request, keeping the underlying error for logs
+#[derive(Debug, Clone)]
+pub struct NetworkError {
+    pub kind: NetworkErrorKind,
+    pub message: String,
+    source: Option<Arc<dyn std::error::Error + Send + Sync>>,
+}
+
+impl NetworkError {
+    pub fn new(kind: NetworkErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+            source: None,
+        }
+    }
+
+    /// Attach the error that caused this one
+    pub fn with_source(mut self, source: impl std::error::Error + Send + Sync + 'static) -> Self {
+        self.source = Some(Arc::new(source));
+        self
+    }
+}
+
+impl PartialEq for NetworkError {
+    // Sources aren't comparable; the kind and message identify the failure
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind && self.message == other.message
+    }
+}
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum FetchError {
-    Network(String),
+    Network(NetworkError),
     Parse(String),
-    Auth(String),
+    Auth {
+        /// HTTP status that rejected the credentials, if any
+        status: Option<u16>,
+        message: String,
+    },
+    /// A setting needed to make a request is missing or unusable
+    Config {
+        /// Dotted config key (e.g. `credentials.org_id`)
+        key: String,
+        message: String,
+    },
     RateLimited {
         message: String,
         retry_after: Option<u64>,
@@ -14,9 +150,10 @@ pub enum FetchError {
 impl std::fmt::Display for FetchError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            FetchError::Network(msg) => write!(f, "Network error: {}", msg),
+            FetchError::Network(error) => write!(f, "Network error: {}", error.message),
             FetchError::Parse(msg) => write!(f, "Parse error: {}", msg),
-            FetchError::Auth(msg) => write!(f, "Auth error: {}", msg),
+            FetchError::Auth { message, .. } => write!(f, "Auth error: {}", message),
+            FetchError::Config { key, message } => write!(f, "Config error: {} {}", key, message),
             FetchError::RateLimited {
                 message,
                 retry_after,
@@ -31,38 +168,104 @@ impl std::fmt::Display for FetchError {
     }
 }
 
-impl std::error::Error for FetchError {}
+impl std::error::Error for FetchError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FetchError::Network(NetworkError {
+                source: Some(source),
+                ..
+            }) => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
 
 impl FetchError {
+    /// A network error of `kind` without an underlying cause
+    pub fn network(kind: NetworkErrorKind, message: impl Into<String>) -> Self {
+        FetchError::Network(NetworkError::new(kind, message))
+    }
+
+    /// A failed request, classified from the request error
+    pub fn request(action: &str, error: wreq::Error) -> Self {
+        let kind = NetworkErrorKind::classify(&error);
+        let message = if kind == NetworkErrorKind::Timeout {
+            format!("Timed out while trying to {}: {}", action, error)
+        } else {
+            format!("Failed to {}: {}", action, error)
+        };
+        FetchError::Network(NetworkError::new(kind, message).with_source(error))
+    }
+
+    /// Rejected credentials, without an HTTP status
+    pub fn auth(message: impl Into<String>) -> Self {
+        FetchError::Auth {
+            status: None,
+            message: message.into(),
+        }
+    }
+
+    /// A required setting that isn't set, naming where it can be set
+    pub fn missing_config(key: &str) -> Self {
+        let message = match env_var_for(key) {
+            Some(var) => format!("is not set in the config file or {}", var),
+            None => "is not set in the config file".to_string(),
+        };
+        FetchError::Config {
+            key: key.to_string(),
+            message,
+        }
+    }
+
+    /// Network error kind, if this is a network error
+    pub fn network_kind(&self) -> Option<NetworkErrorKind> {
+        match self {
+            FetchError::Network(error) => Some(error.kind),
+            _ => None,
+        }
+    }
+
+    /// HTTP status of the response that failed, if there was one
+    pub fn http_status(&self) -> Option<u16> {
+        match self {
+            FetchError::Network(NetworkError {
+                kind: NetworkErrorKind::HttpStatus(code),
+                ..
+            }) => Some(*code),
+            FetchError::Auth { status, .. } => *status,
+            FetchError::RateLimited { .. } => Some(429),
+            _ => None,
+        }
+    }
+
+    /// Short name for logs and diagnostics (e.g. "auth", "timeout", "http_503")
+    pub fn kind_name(&self) -> Cow<'static, str> {
+        match self {
+            FetchError::Network(error) => error.kind.as_str(),
+            FetchError::Parse(_) => "parse".into(),
+            FetchError::Auth { .. } => "auth".into(),
+            FetchError::Config { .. } => "config".into(),
+            FetchError::RateLimited { .. } => "rate_limited".into(),
+        }
+    }
+
     /// Returns true if the error is transient and should be retried
     pub fn is_transient(&self) -> bool {
         match self {
-            FetchError::Network(msg) => {
-                // Some network errors are not transient and should not be retried
-                let msg_lower = msg.to_lowercase();
-
-                // Non-retryable network errors:
-                // - SSL/TLS certificate errors
-                // - DNS resolution failures
-                // - Invalid URLs or malformed requests
-                let non_transient_patterns = [
-                    "certificate",
-                    "cert",
-                    "ssl",
-                    "tls",
-                    "dns",
-                    "invalid url",
-                    "malformed",
-                    "invalid header",
-                ];
-
-                // If the error message contains any non-transient pattern, it's not transient
-                !non_transient_patterns
-                    .iter()
-                    .any(|pattern| msg_lower.contains(pattern))
-            }
+            FetchError::Network(error) => match error.kind {
+                NetworkErrorKind::Timeout
+                | NetworkErrorKind::ConnectionRefused
+                | NetworkErrorKind::Dns
+                | NetworkErrorKind::Connect
+                | NetworkErrorKind::BodyRead => true,
+                // Server-side failures and request timeouts clear up on their own
+                NetworkErrorKind::HttpStatus(code) => code >= 500 || code == 408,
+                // Retrying won't fix a bad certificate or a malformed request
+                NetworkErrorKind::Tls | NetworkErrorKind::InvalidRequest => false,
+            },
             FetchError::RateLimited { .. } => true,
-            FetchError::Auth(_) => false,
+            FetchError::Auth { .. } => false,
+            FetchError::Config { .. } => false,
             FetchError::Parse(_) => false,
         }
     }
@@ -70,35 +273,36 @@ impl FetchError {
     /// Get a user-friendly error category for display
     pub fn category(&self) -> &'static str {
         match self {
-            FetchError::Network(_) => "Offline",
+            FetchError::Network(error) => match error.kind {
+                NetworkErrorKind::Tls => "TLS Error",
+                NetworkErrorKind::HttpStatus(code) if code >= 500 => "Server Error",
+                NetworkErrorKind::HttpStatus(_) => "HTTP Error",
+                NetworkErrorKind::InvalidRequest => "Request Error",
+                _ => "Offline",
+            },
             FetchError::RateLimited { .. } => "Rate Limited",
-            FetchError::Auth(_) => "Authentication Error",
+            FetchError::Auth { .. } => "Authentication Error",
+            FetchError::Config { .. } => "Not Configured",
             FetchError::Parse(_) => "Parse Error",
         }
     }
 }
 
-impl From<std::env::VarError> for FetchError {
-    fn from(e: std::env::VarError) -> Self {
-        FetchError::Auth(format!("Missing environment variable: {}", e))
-    }
-}
-
 impl From<wreq::header::InvalidHeaderValue> for FetchError {
     fn from(e: wreq::header::InvalidHeaderValue) -> Self {
-        FetchError::Network(format!("Invalid header value: {}", e))
+        FetchError::Network(
+            NetworkError::new(
+                NetworkErrorKind::InvalidRequest,
+                format!("Invalid header value: {}", e),
+            )
+            .with_source(e),
+        )
     }
 }
 
 impl From<wreq::Error> for FetchError {
     fn from(e: wreq::Error) -> Self {
-        FetchError::Network(format!("Request failed: {}", e))
-    }
-}
-
-impl From<String> for FetchError {
-    fn from(s: String) -> Self {
-        FetchError::Network(s)
+        FetchError::request("complete request", e)
     }
 }
 
@@ -109,14 +313,24 @@ pub enum ErrorIndicator {
     Offline,     // Gray border - network/transient errors
     AuthError,   // Yellow border - authentication failures
     RateLimited, // Orange border - rate limiting
+    ConfigError, // Blue border - setup needed, polling stopped
 }
 
 impl ErrorIndicator {
     pub fn from_error(error: Option<&FetchError>) -> Self {
         match error {
             None => ErrorIndicator::None,
+            // Problems that need the user's attention rather than a working network
+            Some(FetchError::Network(NetworkError {
+                kind:
+                    NetworkErrorKind::Tls
+                    | NetworkErrorKind::InvalidRequest
+                    | NetworkErrorKind::HttpStatus(400..=499),
+                ..
+            })) => ErrorIndicator::AuthError,
             Some(FetchError::Network(_)) => ErrorIndicator::Offline,
-            Some(FetchError::Auth(_)) => ErrorIndicator::AuthError,
+            Some(FetchError::Auth { .. }) => ErrorIndicator::AuthError,
+            Some(FetchError::Config { .. }) => ErrorIndicator::ConfigError,
             Some(FetchError::RateLimited { .. }) => ErrorIndicator::RateLimited,
             Some(FetchError::Parse(_)) => ErrorIndicator::AuthError,
         }
@@ -128,6 +342,7 @@ impl ErrorIndicator {
             ErrorIndicator::Offline => Some([128, 128, 128]),
             ErrorIndicator::AuthError => Some([255, 193, 7]),
             ErrorIndicator::RateLimited => Some([255, 152, 0]),
+            ErrorIndicator::ConfigError => Some([33, 150, 243]),
         }
     }
 }
@@ -0,0 +1,406 @@
+use crate::poller::Momentum;
+use crate::provider::{MetricDescriptor, MetricRole, UsageSnapshot};
+use crate::state::UsagePeriod;
+use chrono::{DateTime, TimeZone, Utc};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// How far back stored history is used to estimate the pace of long window metrics
+pub const LONG_WINDOW_LOOKBACK: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Shortest stretch of history a pace estimate is based on
+const MIN_RATE_SPAN: Duration = Duration::from_secs(60);
+
+/// Projection of when a metric will reach 100% at the current pace
+#[derive(Debug, Clone, PartialEq)]
+pub struct Forecast {
+    pub metric_id: String,
+    pub utilization: f64,
+    /// Percentage points per hour
+    pub rate_per_hour: f64,
+    /// Time until 100% at the current pace (None when usage isn't increasing)
+    pub time_to_limit: Option<Duration>,
+    /// Time until the window resets (None when unknown or already passed)
+    pub resets_in: Option<Duration>,
+}
+
+impl Forecast {
+    pub fn new(
+        metric_id: &str,
+        period: &UsagePeriod,
+        rate_per_hour: f64,
+        now: DateTime<Utc>,
+    ) -> Self {
+        let remaining = (100.0 - period.utilization).max(0.0);
+        let time_to_limit = if remaining == 0.0 {
+            Some(Duration::ZERO)
+        } else if rate_per_hour > 0.0 {
+            Duration::try_from_secs_f64(remaining / rate_per_hour * 3600.0).ok()
+        } else {
+            None
+        };
+
+        let resets_in = period
+            .resets_at
+            .and_then(|resets_at| (resets_at - now).to_std().ok());
+
+        Self {
+            metric_id: metric_id.to_string(),
+            utilization: period.utilization,
+            rate_per_hour,
+            time_to_limit,
+            resets_in,
+        }
+    }
+
+    /// Whether the limit will be hit before the window resets
+    pub fn exceeds_before_reset(&self) -> bool {
+        match (self.time_to_limit, self.resets_in) {
+            (Some(limit), Some(reset)) => limit < reset,
+            // Without a known reset, any projected hit counts
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+    }
+
+    /// Tooltip line, or None when usage isn't increasing
+    pub fn describe(&self) -> Option<String> {
+        let limit = self.time_to_limit?;
+        let flag = if self.exceeds_before_reset() {
+            "⚠ "
+        } else {
+            ""
+        };
+
+        if limit.is_zero() {
+            return Some(match self.resets_in {
+                Some(reset) => format!(
+                    "{}limit reached, resets in {}",
+                    flag,
+                    format_duration(reset, DurationStyle::Compact)
+                ),
+                None => format!("{}limit reached", flag),
+            });
+        }
+
+        let reset = match self.resets_in {
+            Some(reset) if limit < reset => {
+                format!(
+                    ", before the reset in {}",
+                    format_duration(reset, DurationStyle::Compact)
+                )
+            }
+            Some(reset) => format!(
+                ", after the reset in {}",
+                format_duration(reset, DurationStyle::Compact)
+            ),
+            None => String::new(),
+        };
+        Some(format!(
+            "{}at current pace: 100% in ~{}{}",
+            flag,
+            format_duration(limit, DurationStyle::Compact),
+            reset
+        ))
+    }
+}
+
+/// Percentage points per hour from an increase over a span
+pub fn rate_per_hour(increase: f64, span: Duration) -> Option<f64> {
+    (span >= MIN_RATE_SPAN).then(|| increase / span.as_secs_f64() * 3600.0)
+}
+
+/// Pace from time-ordered (timestamp, utilization) samples
+///
+/// Only increases are counted, so a window reset in the middle of the
+/// samples doesn't read as negative usage.
+pub fn history_rate(samples: &[(DateTime<Utc>, f64)]) -> Option<f64> {
+    let (first, last) = (samples.first()?, samples.last()?);
+    let span = (last.0 - first.0).to_std().ok()?;
+    let increase: f64 = samples.windows(2).map(|w| (w[1].1 - w[0].1).max(0.0)).sum();
+    rate_per_hour(increase, span)
+}
+
+/// Forecast every metric in the snapshot
+///
+/// Short window metrics use the poller's recent momentum for that metric.
+/// Long window and auxiliary metrics move slowly, so they prefer the pace from
+/// stored history (`long_term_rates`, keyed by metric id) and fall back to the
+/// momentum. Periods without a descriptor are treated as auxiliary.
+pub fn forecast_snapshot(
+    descriptors: &[MetricDescriptor],
+    snapshot: &UsageSnapshot,
+    momentum: &Momentum,
+    long_term_rates: &BTreeMap<String, f64>,
+    now: DateTime<Utc>,
+) -> Vec<Forecast> {
+    let described = descriptors.iter().filter_map(|descriptor| {
+        Some((descriptor.id, descriptor.role, snapshot.get(descriptor.id)?))
+    });
+    let undescribed = snapshot
+        .undescribed(descriptors)
+        .map(|(id, period)| (id, MetricRole::Auxiliary, period));
+
+    described
+        .chain(undescribed)
+        .map(|(id, role, period)| {
+            let recent = rate_per_hour(momentum.get(id).as_pct(), momentum.span);
+            let rate = match role {
+                MetricRole::ShortWindow => recent,
+                MetricRole::LongWindow | MetricRole::Auxiliary => {
+                    long_term_rates.get(id).copied().or(recent)
+                }
+            };
+            Forecast::new(id, period, rate.unwrap_or(0.0), now)
+        })
+        .collect()
+}
+
+/// How `format_duration` lays out its units
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DurationStyle {
+    /// Units run together, for forecast lines (e.g. "2h10m")
+    Compact,
+    /// Units separated by a space, for countdowns (e.g. "3h 12m")
+    Spaced,
+    /// Spaced and down to the second, for simulation reports (e.g. "3m 20s")
+    Precise,
+}
+
+/// Human-readable duration (e.g. "2h10m", "45m", "2d4h")
+///
+/// Shows the largest non-zero unit and the one below it when that isn't
+/// zero. Only `Precise` goes below minutes; the others show "<1m" instead.
+pub fn format_duration(duration: Duration, style: DurationStyle) -> String {
+    let (separator, precision, fallback) = match style {
+        DurationStyle::Compact => ("", 3, "<1m"),
+        DurationStyle::Spaced => (" ", 3, "<1m"),
+        DurationStyle::Precise => (" ", 4, "0s"),
+    };
+    let secs = duration.as_secs();
+    let units = [
+        (secs / 86400, "d"),
+        (secs / 3600 % 24, "h"),
+        (secs / 60 % 60, "m"),
+        (secs % 60, "s"),
+    ];
+    let units = &units[..precision];
+
+    let Some(first) = units.iter().position(|(amount, _)| *amount > 0) else {
+        return fallback.to_string();
+    };
+    units[first..]
+        .iter()
+        .take(2)
+        .enumerate()
+        .filter(|(index, (amount, _))| *index == 0 || *amount > 0)
+        .map(|(_, (amount, unit))| format!("{}{}", amount, unit))
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+/// Resets further away than this are shown as a weekday and time
+const COUNTDOWN_LIMIT: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Reset time in `tz` as a weekday and time (e.g. "Thu 14:00")
+pub fn format_reset_time<Tz: TimeZone>(resets_at: DateTime<Utc>, tz: &Tz) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    resets_at.with_timezone(tz).format("%a %H:%M").to_string()
+}
+
+/// When a window resets, relative to `now` ("resets in 3h 12m", "resets Thu 14:00")
+pub fn describe_reset<Tz: TimeZone>(resets_at: DateTime<Utc>, now: DateTime<Utc>, tz: &Tz) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    match (resets_at - now).to_std() {
+        Ok(remaining) if remaining < COUNTDOWN_LIMIT => {
+            format!(
+                "resets in {}",
+                format_duration(remaining, DurationStyle::Spaced)
+            )
+        }
+        Ok(_) => format!("resets {}", format_reset_time(resets_at, tz)),
+        Err(_) => "reset due".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poller::Permille;
+    use assert2::{assert, let_assert};
+    use chrono::Duration as ChronoDuration;
+    use rstest::rstest;
+
+    fn period(
+        utilization: f64,
+        resets_in: Option<ChronoDuration>,
+        now: DateTime<Utc>,
+    ) -> UsagePeriod {
+        UsagePeriod {
+            utilization,
+            resets_at: resets_in.map(|d| now + d),
+        }
+    }
+
+    #[rstest]
+    #[case(Duration::from_secs(30), "<1m")]
+    #[case(Duration::from_secs(45 * 60), "45m")]
+    #[case(Duration::from_secs(3 * 3600), "3h")]
+    #[case(Duration::from_secs(2 * 3600 + 10 * 60), "2h10m")]
+    #[case(Duration::from_secs(2 * 86400 + 4 * 3600 + 59), "2d4h")]
+    #[case(Duration::from_secs(86400 + 30 * 60), "1d")]
+    fn test_format_duration(#[case] duration: Duration, #[case] expected: &str) {
+        assert!(format_duration(duration, DurationStyle::Compact) == expected);
+    }
+
+    #[rstest]
+    #[case(Duration::from_secs(3 * 3600 + 12 * 60), "3h 12m")]
+    #[case(Duration::from_secs(2 * 86400 + 4 * 3600), "2d 4h")]
+    #[case(Duration::from_secs(45 * 60), "45m")]
+    #[case(Duration::from_secs(59), "<1m")]
+    fn test_format_duration_spaced(#[case] duration: Duration, #[case] expected: &str) {
+        assert!(format_duration(duration, DurationStyle::Spaced) == expected);
+    }
+
+    #[rstest]
+    #[case(Duration::from_secs(45), "45s")]
+    #[case(Duration::from_secs(200), "3m 20s")]
+    #[case(Duration::from_secs(3900), "1h 5m")]
+    #[case(Duration::from_secs(3 * 86400 + 1), "3d")]
+    #[case(Duration::ZERO, "0s")]
+    fn test_format_duration_precise(#[case] duration: Duration, #[case] expected: &str) {
+        assert!(format_duration(duration, DurationStyle::Precise) == expected);
+    }
+
+    #[rstest]
+    #[case(ChronoDuration::minutes(3 * 60 + 12), "resets in 3h 12m")]
+    #[case(ChronoDuration::minutes(45), "resets in 45m")]
+    #[case(ChronoDuration::seconds(20), "resets in <1m")]
+    #[case(ChronoDuration::hours(23), "resets in 23h")]
+    #[case(ChronoDuration::hours(50), "resets Thu 16:00")]
+    #[case(ChronoDuration::minutes(-5), "reset due")]
+    fn test_describe_reset(#[case] until: ChronoDuration, #[case] expected: &str) {
+        // Tuesday 12:00 UTC, shown in UTC+2
+        let now: DateTime<Utc> = "2025-11-25T12:00:00Z".parse().unwrap();
+        let tz = chrono::FixedOffset::east_opt(2 * 3600).unwrap();
+        assert!(describe_reset(now + until, now, &tz) == expected);
+    }
+
+    #[test]
+    fn test_forecast_crosses_limit_before_reset() {
+        let now = Utc::now();
+        // 74% used, 13 points/hour: 26 points left takes 2h
+        let forecast = Forecast::new(
+            "five_hour",
+            &period(74.0, Some(ChronoDuration::hours(3)), now),
+            13.0,
+            now,
+        );
+
+        assert!(forecast.time_to_limit == Some(Duration::from_secs(2 * 3600)));
+        assert!(forecast.exceeds_before_reset());
+        let_assert!(Some(line) = forecast.describe());
+        assert!(line == "⚠ at current pace: 100% in ~2h, before the reset in 3h");
+    }
+
+    #[test]
+    fn test_forecast_resets_before_limit() {
+        let now = Utc::now();
+        let forecast = Forecast::new(
+            "seven_day",
+            &period(40.0, Some(ChronoDuration::hours(10)), now),
+            1.0,
+            now,
+        );
+
+        assert!(!forecast.exceeds_before_reset());
+        let_assert!(Some(line) = forecast.describe());
+        assert!(line == "at current pace: 100% in ~2d12h, after the reset in 10h");
+    }
+
+    #[test]
+    fn test_forecast_idle_has_no_line() {
+        let now = Utc::now();
+        let forecast = Forecast::new("five_hour", &period(50.0, None, now), 0.0, now);
+
+        assert!(forecast.time_to_limit.is_none());
+        assert!(!forecast.exceeds_before_reset());
+        assert!(forecast.describe().is_none());
+    }
+
+    #[test]
+    fn test_forecast_at_limit() {
+        let now = Utc::now();
+        let forecast = Forecast::new(
+            "five_hour",
+            &period(100.0, Some(ChronoDuration::minutes(90)), now),
+            0.0,
+            now,
+        );
+
+        assert!(forecast.exceeds_before_reset());
+        let_assert!(Some(line) = forecast.describe());
+        assert!(line == "⚠ limit reached, resets in 1h30m");
+    }
+
+    #[test]
+    fn test_history_rate_ignores_resets() {
+        let start = Utc::now();
+        let at = |hours| start + ChronoDuration::hours(hours);
+        // +10 over the first two hours, a reset, then +4 over the last two
+        let samples = [(at(0), 80.0), (at(2), 90.0), (at(3), 0.0), (at(4), 4.0)];
+
+        let_assert!(Some(rate) = history_rate(&samples));
+        assert!(rate == 3.5);
+        assert!(history_rate(&samples[..1]).is_none());
+    }
+
+    #[test]
+    fn test_forecast_snapshot_picks_rate_by_role() {
+        let now = Utc::now();
+        let descriptors = [
+            MetricDescriptor {
+                id: "five_hour",
+                label: "5-hour",
+                role: MetricRole::ShortWindow,
+            },
+            MetricDescriptor {
+                id: "seven_day",
+                label: "Weekly",
+                role: MetricRole::LongWindow,
+            },
+        ];
+        let snapshot = UsageSnapshot {
+            periods: BTreeMap::from([
+                ("five_hour".to_string(), period(20.0, None, now)),
+                ("seven_day".to_string(), period(30.0, None, now)),
+                ("new_bucket".to_string(), period(5.0, None, now)),
+            ]),
+            http_status: None,
+        };
+        let momentum = Momentum {
+            metrics: BTreeMap::from([
+                ("five_hour".to_string(), Permille::from_whole(6)),
+                ("seven_day".to_string(), Permille::from_whole(1)),
+                ("new_bucket".to_string(), Permille::from_whole(3)),
+            ]),
+            span: Duration::from_secs(1800),
+        };
+
+        let forecasts =
+            forecast_snapshot(&descriptors, &snapshot, &momentum, &BTreeMap::new(), now);
+        assert!(forecasts[0].rate_per_hour == 12.0);
+        assert!(forecasts[1].rate_per_hour == 2.0);
+        // Undescribed buckets are forecast last, from their own momentum
+        assert!(forecasts[2].metric_id == "new_bucket");
+        assert!(forecasts[2].rate_per_hour == 6.0);
+
+        let long_term = BTreeMap::from([("seven_day".to_string(), 0.5)]);
+        let forecasts = forecast_snapshot(&descriptors, &snapshot, &momentum, &long_term, now);
+        assert!(forecasts[1].rate_per_hour == 0.5);
+    }
+}
@@ -0,0 +1,468 @@
+use crate::poller::{Permille, TemperatureState, Transition};
+use crate::state::UsagePeriod;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use rusqlite::{Connection, params};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::debug;
+
+const HISTORY_FILE_NAME: &str = "history.sqlite3";
+
+/// How often old records are pruned while the app is running
+const PRUNE_INTERVAL: ChronoDuration = ChronoDuration::hours(1);
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS fetches (
+    id            INTEGER PRIMARY KEY AUTOINCREMENT,
+    provider      TEXT    NOT NULL,
+    timestamp_ms  INTEGER NOT NULL,
+    poller_state  TEXT    NOT NULL,
+    interval_secs INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS fetches_provider_timestamp
+    ON fetches (provider, timestamp_ms);
+
+CREATE TABLE IF NOT EXISTS periods (
+    fetch_id    INTEGER NOT NULL REFERENCES fetches (id) ON DELETE CASCADE,
+    metric_id   TEXT    NOT NULL,
+    utilization REAL    NOT NULL,
+    resets_at   TEXT,
+    PRIMARY KEY (fetch_id, metric_id)
+);
+
+CREATE TABLE IF NOT EXISTS transitions (
+    fetch_id  INTEGER NOT NULL REFERENCES fetches (id) ON DELETE CASCADE,
+    metric_id TEXT    NOT NULL,
+    kind      TEXT    NOT NULL,
+    from_pct  REAL    NOT NULL,
+    to_pct    REAL    NOT NULL,
+    PRIMARY KEY (fetch_id, metric_id)
+);
+";
+
+/// Configuration for the persistent usage history
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HistoryConfig {
+    pub enabled: bool,
+    /// Records older than this are deleted
+    pub retention_days: u32,
+    /// Database location (defaults to the platform data directory)
+    pub path: Option<PathBuf>,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            retention_days: 90,
+            path: None,
+        }
+    }
+}
+
+impl HistoryConfig {
+    /// Resolve the database path, falling back to the platform data directory
+    pub fn resolved_path(&self) -> Option<PathBuf> {
+        self.path.clone().or_else(|| {
+            dirs::data_dir().map(|dir| dir.join("usage-indicator").join(HISTORY_FILE_NAME))
+        })
+    }
+}
+
+/// A single successful fetch as stored in the history
+#[derive(Debug, Clone, PartialEq)]
+pub struct FetchRecord {
+    pub timestamp: DateTime<Utc>,
+    /// Usage periods keyed by metric id
+    pub periods: BTreeMap<String, UsagePeriod>,
+    pub poller_state: TemperatureState,
+    /// Interval chosen for the next poll after this fetch
+    pub interval: Duration,
+    /// How metrics moved since the previous fetch (unchanged metrics are omitted)
+    pub transitions: Vec<Transition>,
+}
+
+/// SQLite-backed store of every successful fetch
+pub struct HistoryStore {
+    conn: Connection,
+    provider: String,
+    retention: ChronoDuration,
+    last_pruned: Option<DateTime<Utc>>,
+}
+
+impl HistoryStore {
+    /// Open (or create) the history database at `path`
+    pub fn open(path: &Path, provider: &str, retention_days: u32) -> Result<Self, rusqlite::Error> {
+        if let Some(parent) = path.parent() {
+            // Let `Connection::open` report the failure if this doesn't work
+            let _ = std::fs::create_dir_all(parent);
+        }
+        Self::with_connection(Connection::open(path)?, provider, retention_days)
+    }
+
+    /// Open a throwaway in-memory store (for testing)
+    pub fn open_in_memory(provider: &str, retention_days: u32) -> Result<Self, rusqlite::Error> {
+        Self::with_connection(Connection::open_in_memory()?, provider, retention_days)
+    }
+
+    fn with_connection(
+        conn: Connection,
+        provider: &str,
+        retention_days: u32,
+    ) -> Result<Self, rusqlite::Error> {
+        conn.pragma_update(None, "foreign_keys", true)?;
+        conn.execute_batch(SCHEMA)?;
+
+        Ok(Self {
+            conn,
+            provider: provider.to_string(),
+            // Longer than chrono can represent means keeping everything
+            retention: ChronoDuration::try_days(retention_days.into())
+                .unwrap_or(ChronoDuration::MAX),
+            last_pruned: None,
+        })
+    }
+
+    /// Store a fetch, pruning expired records at most once per `PRUNE_INTERVAL`
+    pub fn record(&mut self, record: &FetchRecord) -> Result<(), rusqlite::Error> {
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "INSERT INTO fetches (provider, timestamp_ms, poller_state, interval_secs)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                self.provider,
+                record.timestamp.timestamp_millis(),
+                record.poller_state.as_str(),
+                record.interval.as_secs() as i64,
+            ],
+        )?;
+        let fetch_id = tx.last_insert_rowid();
+
+        {
+            let mut insert = tx.prepare(
+                "INSERT INTO periods (fetch_id, metric_id, utilization, resets_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            for (metric_id, period) in &record.periods {
+                insert.execute(params![
+                    fetch_id,
+                    metric_id,
+                    period.utilization,
+                    period.resets_at.map(|t| t.to_rfc3339())
+                ])?;
+            }
+
+            let mut insert = tx.prepare(
+                "INSERT INTO transitions (fetch_id, metric_id, kind, from_pct, to_pct)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?;
+            for transition in &record.transitions {
+                insert.execute(params![
+                    fetch_id,
+                    transition.metric,
+                    transition.kind.as_str(),
+                    transition.from.as_pct(),
+                    transition.to.as_pct()
+                ])?;
+            }
+        }
+        tx.commit()?;
+
+        let prune_due = self
+            .last_pruned
+            .is_none_or(|last| record.timestamp - last >= PRUNE_INTERVAL);
+        if prune_due {
+            self.prune(record.timestamp)?;
+        }
+
+        Ok(())
+    }
+
+    /// Delete records older than the retention period, returning how many were removed
+    pub fn prune(&mut self, now: DateTime<Utc>) -> Result<usize, rusqlite::Error> {
+        self.last_pruned = Some(now);
+        // Nothing can be older than a cutoff before the earliest representable time
+        let Some(cutoff) = now.checked_sub_signed(self.retention) else {
+            return Ok(0);
+        };
+        let removed = self.conn.execute(
+            "DELETE FROM fetches WHERE provider = ?1 AND timestamp_ms < ?2",
+            params![self.provider, cutoff.timestamp_millis()],
+        )?;
+
+        if removed > 0 {
+            debug!(removed, "Pruned expired usage history");
+        }
+        Ok(removed)
+    }
+
+    /// Fetch records with `from <= timestamp < to`, oldest first
+    pub fn query(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<FetchRecord>, rusqlite::Error> {
+        let mut fetches = self.conn.prepare(
+            "SELECT id, timestamp_ms, poller_state, interval_secs FROM fetches
+             WHERE provider = ?1 AND timestamp_ms >= ?2 AND timestamp_ms < ?3
+             ORDER BY timestamp_ms",
+        )?;
+        let mut periods = self
+            .conn
+            .prepare("SELECT metric_id, utilization, resets_at FROM periods WHERE fetch_id = ?1")?;
+        let mut transitions = self.conn.prepare(
+            "SELECT metric_id, kind, from_pct, to_pct FROM transitions WHERE fetch_id = ?1
+             ORDER BY metric_id",
+        )?;
+
+        let rows = fetches.query_map(
+            params![
+                self.provider,
+                from.timestamp_millis(),
+                to.timestamp_millis()
+            ],
+            |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, i64>(3)?,
+                ))
+            },
+        )?;
+
+        let mut records = Vec::new();
+        for row in rows {
+            let (id, timestamp_ms, state, interval_secs) = row?;
+
+            let periods = periods
+                .query_map(params![id], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        UsagePeriod {
+                            utilization: row.get(1)?,
+                            resets_at: row
+                                .get::<_, Option<String>>(2)?
+                                .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+                                .map(|t| t.with_timezone(&Utc)),
+                        },
+                    ))
+                })?
+                .collect::<Result<BTreeMap<_, _>, _>>()?;
+
+            let transitions = transitions
+                .query_map(params![id], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, f64>(2)?,
+                        row.get::<_, f64>(3)?,
+                    ))
+                })?
+                .filter_map(|row| match row {
+                    // Unknown kinds can only come from a newer schema; skip them
+                    Ok((metric, kind, from, to)) => Some(Ok(Transition {
+                        metric,
+                        kind: kind.parse().ok()?,
+                        from: Permille::from_pct(from),
+                        to: Permille::from_pct(to),
+                    })),
+                    Err(e) => Some(Err(e)),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            records.push(FetchRecord {
+                timestamp: DateTime::from_timestamp_millis(timestamp_ms).unwrap_or_default(),
+                periods,
+                // Unknown states can only come from a newer schema; treat as idle
+                poller_state: state.parse().unwrap_or(TemperatureState::Cold),
+                interval: Duration::from_secs(interval_secs.max(0) as u64),
+                transitions,
+            });
+        }
+
+        Ok(records)
+    }
+
+    /// Fetch every record newer than `window` before `now`
+    pub fn recent(
+        &self,
+        window: Duration,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<FetchRecord>, rusqlite::Error> {
+        let window = ChronoDuration::from_std(window).unwrap_or(ChronoDuration::MAX);
+        // +1ms so a record taken exactly at `now` is included
+        let from = now
+            .checked_sub_signed(window)
+            .unwrap_or(DateTime::<Utc>::MIN_UTC);
+        self.query(from, now + ChronoDuration::milliseconds(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poller::TransitionKind;
+    use assert2::{assert, let_assert};
+
+    fn record(timestamp: DateTime<Utc>, five_hour: f64, weekly: f64) -> FetchRecord {
+        let period = |utilization| UsagePeriod {
+            utilization,
+            resets_at: Some("2025-11-29T00:00:00Z".parse().unwrap()),
+        };
+        FetchRecord {
+            timestamp,
+            periods: BTreeMap::from([
+                ("five_hour".to_string(), period(five_hour)),
+                ("seven_day".to_string(), period(weekly)),
+            ]),
+            poller_state: TemperatureState::Warm,
+            interval: Duration::from_secs(300),
+            transitions: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_record_and_query_round_trip() {
+        let_assert!(Ok(mut store) = HistoryStore::open_in_memory("claude", 90));
+        let now = Utc::now();
+        let original = record(now, 12.5, 40.0);
+
+        assert!(store.record(&original).is_ok());
+
+        let_assert!(Ok(records) = store.recent(Duration::from_secs(60), now));
+        assert!(records.len() == 1);
+        // Millisecond precision is what gets stored
+        assert!(records[0].timestamp.timestamp_millis() == now.timestamp_millis());
+        assert!(records[0].periods == original.periods);
+        assert!(records[0].poller_state == TemperatureState::Warm);
+        assert!(records[0].interval == Duration::from_secs(300));
+    }
+
+    #[test]
+    fn test_transitions_round_trip() {
+        let_assert!(Ok(mut store) = HistoryStore::open_in_memory("claude", 90));
+        let now = Utc::now();
+        let mut original = record(now, 0.0, 41.0);
+        original.transitions = vec![
+            Transition {
+                metric: "five_hour".to_string(),
+                kind: TransitionKind::Reset,
+                from: Permille::from_tenths(932),
+                to: Permille::ZERO,
+            },
+            Transition {
+                metric: "seven_day".to_string(),
+                kind: TransitionKind::Increase,
+                from: Permille::from_tenths(405),
+                to: Permille::from_tenths(412),
+            },
+        ];
+
+        assert!(store.record(&original).is_ok());
+
+        let_assert!(Ok(records) = store.recent(Duration::from_secs(60), now));
+        let_assert!([stored] = records.as_slice());
+        assert!(stored.transitions == original.transitions);
+    }
+
+    #[test]
+    fn test_query_by_time_range() {
+        let_assert!(Ok(mut store) = HistoryStore::open_in_memory("claude", 90));
+        let base = Utc::now() - ChronoDuration::hours(10);
+
+        for hour in 0..10 {
+            let timestamp = base + ChronoDuration::hours(hour);
+            assert!(store.record(&record(timestamp, hour as f64, 0.0)).is_ok());
+        }
+
+        let_assert!(
+            Ok(records) = store.query(
+                base + ChronoDuration::hours(2),
+                base + ChronoDuration::hours(5)
+            )
+        );
+        let values: Vec<f64> = records
+            .iter()
+            .map(|r| r.periods["five_hour"].utilization)
+            .collect();
+        assert!(values == vec![2.0, 3.0, 4.0]);
+    }
+
+    #[test]
+    fn test_retention_prunes_old_records() {
+        let_assert!(Ok(mut store) = HistoryStore::open_in_memory("claude", 7));
+        let now = Utc::now();
+
+        assert!(
+            store
+                .record(&record(now - ChronoDuration::days(30), 1.0, 1.0))
+                .is_ok()
+        );
+        assert!(
+            store
+                .record(&record(now - ChronoDuration::days(3), 2.0, 2.0))
+                .is_ok()
+        );
+
+        // The second record is more than PRUNE_INTERVAL later, so it prunes the first
+        let_assert!(Ok(records) = store.query(now - ChronoDuration::days(365), now));
+        assert!(records.len() == 1);
+        assert!(records[0].periods["five_hour"].utilization == 2.0);
+
+        // Periods of the pruned fetch are removed along with it
+        let_assert!(
+            Ok(count) = store
+                .conn
+                .query_row("SELECT COUNT(*) FROM periods", [], |row| row
+                    .get::<_, i64>(0))
+        );
+        assert!(count == 2);
+
+        let_assert!(Ok(removed) = store.prune(now + ChronoDuration::days(5)));
+        assert!(removed == 1);
+    }
+
+    #[test]
+    fn test_huge_retention_keeps_everything() {
+        let_assert!(Ok(mut store) = HistoryStore::open_in_memory("claude", u32::MAX));
+        let now = Utc::now();
+        assert!(
+            store
+                .record(&record(now - ChronoDuration::days(3650), 1.0, 1.0))
+                .is_ok()
+        );
+
+        let_assert!(Ok(removed) = store.prune(now));
+        assert!(removed == 0);
+        let_assert!(Ok(records) = store.recent(Duration::MAX, now));
+        assert!(records.len() == 1);
+    }
+
+    #[test]
+    fn test_records_are_scoped_per_provider() {
+        let path = std::env::temp_dir().join(format!(
+            "usage-indicator-history-{}.sqlite3",
+            std::process::id()
+        ));
+        let now = Utc::now();
+
+        {
+            let_assert!(Ok(mut claude) = HistoryStore::open(&path, "claude", 90));
+            assert!(claude.record(&record(now, 1.0, 1.0)).is_ok());
+        }
+
+        let_assert!(Ok(other) = HistoryStore::open(&path, "other", 90));
+        let_assert!(Ok(records) = other.recent(Duration::from_secs(60), now));
+        assert!(records.is_empty());
+
+        let_assert!(Ok(claude) = HistoryStore::open(&path, "claude", 90));
+        let_assert!(Ok(records) = claude.recent(Duration::from_secs(60), now));
+        assert!(records.len() == 1);
+
+        std::fs::remove_file(&path).ok();
+    }
+}
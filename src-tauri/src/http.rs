@@ -0,0 +1,328 @@
+use crate::error::FetchError;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Write;
+#[cfg(unix)]
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{debug, warn};
+use wreq::header::{HeaderMap, HeaderValue, USER_AGENT};
+use wreq::{Client, ClientBuilder};
+
+const COOKIE_FILE_NAME: &str = "cookies.json";
+
+/// Cookie holding the configured session key, which always wins over server-set values
+const SESSION_COOKIE: &str = "sessionKey";
+
+/// Configuration for the HTTP client shared by all fetches
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HttpConfig {
+    /// Time allowed to establish a connection
+    pub connect_timeout_secs: u64,
+    /// Time allowed for a whole request, including reading the response
+    pub timeout_secs: u64,
+    /// How long idle connections are kept open for reuse
+    pub keep_alive_secs: u64,
+    /// Keep server-set cookies (e.g. Cloudflare clearance) across restarts
+    pub persist_cookies: bool,
+    /// Cookie file location (defaults to the platform data directory)
+    pub cookie_path: Option<PathBuf>,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout_secs: 10,
+            timeout_secs: 30,
+            keep_alive_secs: 90,
+            persist_cookies: true,
+            cookie_path: None,
+        }
+    }
+}
+
+impl HttpConfig {
+    /// Resolve the cookie file, or `None` if cookies only live in memory
+    pub fn resolved_cookie_path(&self) -> Option<PathBuf> {
+        if !self.persist_cookies {
+            return None;
+        }
+        self.cookie_path.clone().or_else(|| {
+            dirs::data_dir().map(|dir| dir.join("usage-indicator").join(COOKIE_FILE_NAME))
+        })
+    }
+
+    /// Build a client with these timeouts and keep-alive settings
+    pub fn build_client(&self) -> Result<Client, FetchError> {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            USER_AGENT,
+            HeaderValue::from_static(
+                "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36",
+            ),
+        );
+        let keep_alive = Duration::from_secs(self.keep_alive_secs);
+
+        ClientBuilder::new()
+            .default_headers(headers)
+            .connect_timeout(Duration::from_secs(self.connect_timeout_secs))
+            .timeout(Duration::from_secs(self.timeout_secs))
+            .pool_idle_timeout(keep_alive)
+            .tcp_keepalive(keep_alive)
+            .build()
+            .map_err(|e| FetchError::request("build client", e))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct StoredCookie {
+    value: String,
+    expires: Option<DateTime<Utc>>,
+}
+
+/// Cookies set by the usage API, optionally saved to a file
+///
+/// The app only talks to one host, so cookies are keyed by name alone and
+/// `Domain`/`Path` attributes are ignored.
+#[derive(Debug, Default)]
+pub struct CookieJar {
+    cookies: BTreeMap<String, StoredCookie>,
+    path: Option<PathBuf>,
+}
+
+impl CookieJar {
+    /// Load cookies saved at `path`, starting empty if there are none
+    ///
+    /// Changes are written back to `path`. With no path, cookies only last
+    /// as long as the jar.
+    pub fn open(path: Option<PathBuf>) -> Self {
+        let cookies = path
+            .as_deref()
+            .map(Self::read)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(_, cookie)| cookie.expires.is_none_or(|at| at > Utc::now()))
+            .collect();
+        Self { cookies, path }
+    }
+
+    fn read(path: &Path) -> BTreeMap<String, StoredCookie> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return BTreeMap::new(),
+            Err(e) => {
+                warn!(path = %path.display(), error = %e, "Failed to read cookies");
+                return BTreeMap::new();
+            }
+        };
+        serde_json::from_str(&contents).unwrap_or_else(|e| {
+            warn!(path = %path.display(), error = %e, "Ignoring malformed cookie file");
+            BTreeMap::new()
+        })
+    }
+
+    /// Apply `Set-Cookie` header values received at `now`
+    pub fn store<'a>(
+        &mut self,
+        set_cookies: impl IntoIterator<Item = &'a str>,
+        now: DateTime<Utc>,
+    ) {
+        let mut changed = false;
+        for header in set_cookies {
+            let Some((name, cookie)) = parse_set_cookie(header, now) else {
+                debug!(header, "Ignoring malformed Set-Cookie header");
+                continue;
+            };
+            if name == SESSION_COOKIE {
+                continue;
+            }
+            changed |= if cookie.expires.is_some_and(|at| at <= now) {
+                self.cookies.remove(&name).is_some()
+            } else {
+                self.cookies.insert(name, cookie.clone()) != Some(cookie)
+            };
+        }
+        if changed {
+            self.save();
+        }
+    }
+
+    /// `Cookie` header value for a request at `now`, with `session_key` first
+    pub fn header(&self, session_key: &str, now: DateTime<Utc>) -> String {
+        let mut header = format!("{}={}", SESSION_COOKIE, session_key);
+        for (name, cookie) in &self.cookies {
+            if cookie.expires.is_none_or(|at| at > now) {
+                header.push_str(&format!("; {}={}", name, cookie.value));
+            }
+        }
+        header
+    }
+
+    fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        let result = serde_json::to_string_pretty(&self.cookies)
+            .map_err(std::io::Error::other)
+            .and_then(|json| write_private(path, &json));
+        if let Err(e) = result {
+            warn!(path = %path.display(), error = %e, "Failed to save cookies");
+        }
+    }
+}
+
+/// Write `contents` so only the current user can read it, as with other credentials
+fn write_private(path: &Path, contents: &str) -> std::io::Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+
+    let mut file = options.open(path)?;
+    // `mode` only applies to new files; tighten one saved by an older version
+    #[cfg(unix)]
+    file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    file.write_all(contents.as_bytes())
+}
+
+/// Parse a `Set-Cookie` value into its name and stored form
+///
+/// `Max-Age` takes precedence over `Expires`, as in RFC 6265.
+fn parse_set_cookie(header: &str, now: DateTime<Utc>) -> Option<(String, StoredCookie)> {
+    let mut parts = header.split(';');
+    let (name, value) = parts.next()?.split_once('=')?;
+    let name = name.trim();
+    if name.is_empty() {
+        return None;
+    }
+
+    let mut max_age = None;
+    let mut expires = None;
+    for attribute in parts {
+        let (key, attr_value) = attribute.split_once('=').unwrap_or((attribute, ""));
+        let attr_value = attr_value.trim();
+        match key.trim().to_ascii_lowercase().as_str() {
+            "max-age" => max_age = attr_value.parse::<i64>().ok(),
+            "expires" => {
+                expires = DateTime::parse_from_rfc2822(attr_value)
+                    .ok()
+                    .map(|at| at.with_timezone(&Utc))
+            }
+            _ => {}
+        }
+    }
+    let expires = match max_age {
+        Some(secs) => Some(now + ChronoDuration::seconds(secs.max(0))),
+        None => expires,
+    };
+
+    Some((
+        name.to_string(),
+        StoredCookie {
+            value: value.trim().to_string(),
+            expires,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::{assert, let_assert};
+    use rstest::rstest;
+
+    fn now() -> DateTime<Utc> {
+        "2025-11-22T12:00:00Z".parse().unwrap()
+    }
+
+    #[rstest]
+    #[case("cf_clearance=abc; Path=/; HttpOnly", "cf_clearance", "abc", None)]
+    #[case(
+        "__cf_bm=x.y; Expires=Sat, 22 Nov 2025 12:30:00 GMT; Secure",
+        "__cf_bm",
+        "x.y",
+        Some("2025-11-22T12:30:00Z")
+    )]
+    #[case(
+        "id=1; max-age=60; expires=Sat, 22 Nov 2025 18:00:00 GMT",
+        "id",
+        "1",
+        Some("2025-11-22T12:01:00Z")
+    )]
+    #[case("token=a=b", "token", "a=b", None)]
+    fn test_parse_set_cookie(
+        #[case] header: &str,
+        #[case] name: &str,
+        #[case] value: &str,
+        #[case] expires: Option<&str>,
+    ) {
+        let_assert!(Some((parsed_name, cookie)) = parse_set_cookie(header, now()));
+        assert!(parsed_name == name);
+        assert!(cookie.value == value);
+        assert!(cookie.expires == expires.map(|at| at.parse().unwrap()));
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("no_equals_sign")]
+    #[case("=value")]
+    fn test_parse_set_cookie_rejects_malformed(#[case] header: &str) {
+        assert!(parse_set_cookie(header, now()).is_none());
+    }
+
+    #[test]
+    fn test_jar_header_keeps_configured_session_key() {
+        let mut jar = CookieJar::open(None);
+        jar.store(
+            ["sessionKey=server-value", "cf_clearance=abc", "lang=en"],
+            now(),
+        );
+        assert!(
+            jar.header("configured", now()) == "sessionKey=configured; cf_clearance=abc; lang=en"
+        );
+    }
+
+    #[test]
+    fn test_jar_drops_expired_cookies() {
+        let mut jar = CookieJar::open(None);
+        jar.store(["short=1; Max-Age=60", "long=2"], now());
+        jar.store(["long=2; Max-Age=0"], now());
+
+        assert!(jar.header("key", now()) == "sessionKey=key; short=1");
+        let later = now() + ChronoDuration::seconds(61);
+        assert!(jar.header("key", later) == "sessionKey=key");
+    }
+
+    #[test]
+    fn test_jar_persists_across_opens() {
+        let dir = std::env::temp_dir().join(format!("usage-cookies-{}", std::process::id()));
+        let path = dir.join(COOKIE_FILE_NAME);
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let mut jar = CookieJar::open(Some(path.clone()));
+        jar.store(["cf_clearance=abc"], Utc::now());
+        let reopened = CookieJar::open(Some(path.clone()));
+        assert!(reopened.header("key", Utc::now()) == "sessionKey=key; cf_clearance=abc");
+        #[cfg(unix)]
+        assert!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777 == 0o600);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_malformed_cookie_file_is_ignored() {
+        let path = std::env::temp_dir().join(format!("usage-cookies-{}.json", std::process::id()));
+        std::fs::write(&path, "not json").unwrap();
+
+        let jar = CookieJar::open(Some(path.clone()));
+        assert!(jar.header("key", now()) == "sessionKey=key");
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
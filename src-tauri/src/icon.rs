@@ -1,4 +1,6 @@
 use crate::error::ErrorIndicator;
+use crate::poller::Permille;
+use serde::Deserialize;
 
 // Icon rendering configuration
 pub const ICON_SIZE: u32 = 32; // Final tray icon size
@@ -8,9 +10,33 @@ const RENDER_SIZE: u32 = ICON_SIZE * RENDER_SCALE; // 128px
 // Font sizes (scaled for render resolution)
 const PERCENTAGE_FONT_SIZE: f32 = 124.0; // 31.0 * 4
 const UNKNOWN_FONT_SIZE: f32 = 80.0; // 20.0 * 4
+const OVERAGE_FONT_SIZE: f32 = 56.0; // 14.0 * 4, fits "100+"
+
+/// Background for usage above 100%, distinct from the green-red gradient
+const OVERAGE_COLOR: [u8; 3] = [106, 27, 154]; // #6A1B9A
+
+/// Configuration for tray icon rendering
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IconConfig {
+    /// Metric id shown in the icon (defaults to the provider's long window metric)
+    pub metric: Option<String>,
+    /// Age after which last-known-good data is flagged as stale
+    pub staleness_threshold_secs: u64,
+    /// Decimal places shown for usage in the tooltip and menu (0 or 1); the
+    /// icon itself always shows whole percents
+    pub decimals: u8,
+}
 
-// Staleness threshold (30 minutes)
-pub const STALENESS_THRESHOLD_SECS: u64 = 1800;
+impl Default for IconConfig {
+    fn default() -> Self {
+        Self {
+            metric: None,
+            staleness_threshold_secs: 1800, // 30 minutes
+            decimals: 0,
+        }
+    }
+}
 
 /// Calculate color based on usage percentage with gradient:
 /// 0-50%: Green → Yellow
@@ -101,22 +127,12 @@ fn calculate_centered_position(text_width: f32, text_height: f32, canvas_size: u
     (x, y)
 }
 
-/// Generate icon with usage percentage displayed on color gradient background
-pub fn generate_usage_icon(percentage: u8, error_indicator: ErrorIndicator) -> Vec<u8> {
-    use ab_glyph::{FontRef, PxScale};
-    use image::{Rgba, RgbaImage, imageops};
-    use imageproc::drawing::{draw_hollow_rect_mut, draw_text_mut};
+/// Draw the error indicator's border, if it has one
+fn draw_error_border(img: &mut image::RgbaImage, error_indicator: ErrorIndicator) {
+    use image::Rgba;
+    use imageproc::drawing::draw_hollow_rect_mut;
     use imageproc::rect::Rect;
 
-    // Get background color based on usage
-    let bg_color = usage_to_color(percentage);
-    let mut img = RgbaImage::from_pixel(
-        RENDER_SIZE,
-        RENDER_SIZE,
-        Rgba([bg_color[0], bg_color[1], bg_color[2], 255]),
-    );
-
-    // Draw error indicator border if needed
     if let Some(border_color) = error_indicator.border_color() {
         let border_rgba = Rgba([border_color[0], border_color[1], border_color[2], 255]);
         let border_width = 8; // Scaled for high-res rendering
@@ -125,9 +141,36 @@ pub fn generate_usage_icon(percentage: u8, error_indicator: ErrorIndicator) -> V
         for i in 0..border_width {
             let rect =
                 Rect::at(i as i32, i as i32).of_size(RENDER_SIZE - (i * 2), RENDER_SIZE - (i * 2));
-            draw_hollow_rect_mut(&mut img, rect, border_rgba);
+            draw_hollow_rect_mut(img, rect, border_rgba);
         }
     }
+}
+
+/// Generate icon with usage percentage displayed on color gradient background
+///
+/// Usage above 100% is shown as "100+" on a purple background.
+pub fn generate_usage_icon(usage: Permille, error_indicator: ErrorIndicator) -> Vec<u8> {
+    use ab_glyph::{FontRef, PxScale};
+    use image::{Rgba, RgbaImage, imageops};
+    use imageproc::drawing::draw_text_mut;
+
+    let over_limit = usage.is_over_limit();
+    let percentage = usage.whole_pct();
+
+    // Get background color based on usage
+    let bg_color = if over_limit {
+        OVERAGE_COLOR
+    } else {
+        usage_to_color(percentage)
+    };
+    let mut img = RgbaImage::from_pixel(
+        RENDER_SIZE,
+        RENDER_SIZE,
+        Rgba([bg_color[0], bg_color[1], bg_color[2], 255]),
+    );
+
+    // Draw error indicator border if needed
+    draw_error_border(&mut img, error_indicator);
 
     // Get contrasting text color
     let text_color = contrast_text_color(bg_color);
@@ -138,10 +181,14 @@ pub fn generate_usage_icon(percentage: u8, error_indicator: ErrorIndicator) -> V
     let font = FontRef::try_from_slice(font_data).expect("Failed to load font");
 
     // Format percentage text
-    let text = format!("{:2}", percentage);
+    let (text, font_size) = if over_limit {
+        ("100+".to_string(), OVERAGE_FONT_SIZE)
+    } else {
+        (format!("{:2}", percentage), PERCENTAGE_FONT_SIZE)
+    };
 
     // Use scaled font size for high-resolution rendering
-    let scale = PxScale::from(PERCENTAGE_FONT_SIZE);
+    let scale = PxScale::from(font_size);
 
     // Measure text dimensions
     let (text_width, text_height) = measure_text_bounds(&text, &font, scale);
@@ -159,13 +206,14 @@ pub fn generate_usage_icon(percentage: u8, error_indicator: ErrorIndicator) -> V
 }
 
 /// Generate icon with question mark for unknown state
-pub fn generate_unknown_icon() -> Vec<u8> {
+pub fn generate_unknown_icon(error_indicator: ErrorIndicator) -> Vec<u8> {
     use ab_glyph::{FontRef, PxScale};
     use image::{Rgba, RgbaImage, imageops};
     use imageproc::drawing::draw_text_mut;
 
     // Gray background for unknown state
     let mut img = RgbaImage::from_pixel(RENDER_SIZE, RENDER_SIZE, Rgba([128, 128, 128, 255]));
+    draw_error_border(&mut img, error_indicator);
 
     // White question mark
     let text_rgba = Rgba([255, 255, 255, 255]);
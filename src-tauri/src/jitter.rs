@@ -0,0 +1,194 @@
+use serde::Deserialize;
+use std::hash::{BuildHasher, RandomState};
+use std::time::Duration;
+
+/// How randomness is mixed into retry delays and polling intervals
+///
+/// Without jitter, clients that fail together (e.g. after a VPN drop) retry
+/// in lockstep. The strategies follow the AWS "Exponential Backoff And
+/// Jitter" article.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JitterKind {
+    /// Use delays as computed
+    #[default]
+    None,
+    /// Anywhere between the minimum and the computed delay
+    Full,
+    /// Between half the computed delay and the full delay
+    Equal,
+    /// Between the minimum and three times the previous delay
+    Decorrelated,
+}
+
+impl JitterKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JitterKind::None => "none",
+            JitterKind::Full => "full",
+            JitterKind::Equal => "equal",
+            JitterKind::Decorrelated => "decorrelated",
+        }
+    }
+}
+
+/// Small seedable PRNG (SplitMix64)
+///
+/// Good enough to spread out delays, and a fixed seed makes tests and
+/// simulations reproducible.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn seeded(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Seed from the OS-provided randomness behind `HashMap`
+    pub fn from_entropy() -> Self {
+        Self::seeded(RandomState::new().hash_one(0u8))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform duration in `low..=high`, to the millisecond
+    pub fn duration_between(&mut self, low: Duration, high: Duration) -> Duration {
+        if high <= low {
+            return low;
+        }
+        let span = (high - low).as_millis().min(u64::MAX as u128 - 1) as u64;
+        low + Duration::from_millis(self.next_u64() % (span + 1))
+    }
+}
+
+/// A `JitterKind` with the random state it needs
+#[derive(Debug, Clone)]
+pub struct Jitter {
+    kind: JitterKind,
+    rng: Rng,
+    /// Last jittered delay, for `Decorrelated`
+    previous: Option<Duration>,
+}
+
+impl Jitter {
+    pub fn new(kind: JitterKind) -> Self {
+        Self {
+            kind,
+            rng: Rng::from_entropy(),
+            previous: None,
+        }
+    }
+
+    pub fn kind(&self) -> JitterKind {
+        self.kind
+    }
+
+    pub fn set_kind(&mut self, kind: JitterKind) {
+        if kind != self.kind {
+            self.previous = None;
+        }
+        self.kind = kind;
+    }
+
+    /// Restart the random sequence from `seed`
+    pub fn reseed(&mut self, seed: u64) {
+        self.rng = Rng::seeded(seed);
+    }
+
+    /// Forget the previous delay, so `Decorrelated` starts over from the minimum
+    pub fn reset(&mut self) {
+        self.previous = None;
+    }
+
+    /// Randomize `delay`, keeping the result within `floor..=cap`
+    pub fn apply(&mut self, delay: Duration, floor: Duration, cap: Duration) -> Duration {
+        let floor = floor.min(cap);
+        let jittered = match self.kind {
+            JitterKind::None => return delay,
+            JitterKind::Full => self.rng.duration_between(floor, delay),
+            JitterKind::Equal => self.rng.duration_between(delay / 2, delay),
+            JitterKind::Decorrelated => {
+                let previous = self.previous.unwrap_or(floor);
+                self.rng.duration_between(floor, previous.saturating_mul(3))
+            }
+        }
+        .clamp(floor, cap);
+
+        self.previous = Some(jittered);
+        jittered
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::assert;
+    use rstest::rstest;
+
+    const FLOOR: Duration = Duration::from_secs(5);
+    const CAP: Duration = Duration::from_secs(300);
+
+    fn seeded(kind: JitterKind) -> Jitter {
+        let mut jitter = Jitter::new(kind);
+        jitter.reseed(42);
+        jitter
+    }
+
+    #[test]
+    fn test_same_seed_same_sequence() {
+        let mut a = seeded(JitterKind::Full);
+        let mut b = seeded(JitterKind::Full);
+        for _ in 0..10 {
+            let delay = Duration::from_secs(60);
+            assert!(a.apply(delay, FLOOR, CAP) == b.apply(delay, FLOOR, CAP));
+        }
+    }
+
+    #[rstest]
+    #[case(JitterKind::Full, FLOOR, Duration::from_secs(80))]
+    #[case(JitterKind::Equal, Duration::from_secs(40), Duration::from_secs(80))]
+    #[case(JitterKind::Decorrelated, FLOOR, CAP)]
+    fn test_jitter_stays_in_range(
+        #[case] kind: JitterKind,
+        #[case] low: Duration,
+        #[case] high: Duration,
+    ) {
+        let mut jitter = seeded(kind);
+        let mut distinct = std::collections::BTreeSet::new();
+        for _ in 0..200 {
+            let delay = jitter.apply(Duration::from_secs(80), FLOOR, CAP);
+            assert!(delay >= low && delay <= high);
+            distinct.insert(delay);
+        }
+        assert!(distinct.len() > 100);
+    }
+
+    #[test]
+    fn test_decorrelated_grows_from_previous_delay() {
+        let mut jitter = seeded(JitterKind::Decorrelated);
+        let first = jitter.apply(Duration::from_secs(80), FLOOR, CAP);
+        // The first delay can be at most three times the floor
+        assert!(first <= FLOOR * 3);
+
+        jitter.reset();
+        for _ in 0..50 {
+            let previous = jitter.previous.unwrap_or(FLOOR);
+            let delay = jitter.apply(Duration::from_secs(80), FLOOR, CAP);
+            assert!(delay <= (previous * 3).min(CAP));
+        }
+    }
+
+    #[test]
+    fn test_none_keeps_delay() {
+        let mut jitter = seeded(JitterKind::None);
+        assert!(jitter.apply(Duration::from_secs(80), FLOOR, CAP) == Duration::from_secs(80));
+    }
+}
@@ -1,20 +1,59 @@
 mod api;
 mod app;
+mod breaker;
+mod cli;
+mod commands;
+mod config;
+mod diagnostics;
+mod engine;
 mod error;
 mod events;
+mod forecast;
+mod history;
+mod http;
 mod icon;
+mod jitter;
+mod notify;
 mod poller;
 mod polling;
+mod provider;
 mod retry;
+mod schema;
+mod simulate;
 mod state;
+mod strategy;
 mod tray;
 
 // Public re-exports
+pub use api::ClaudeProvider;
 pub use app::run;
-pub use error::{ErrorIndicator, FetchError};
+pub use breaker::{BreakerConfig, BreakerState, CircuitBreaker};
+pub use cli::CliArgs;
+pub use commands::{PollCommand, PollSchedule};
+pub use config::{
+    AppConfig, ConfigError, ConfigIssue, ConfigLoader, ConfigOrigin, CredentialsConfig,
+    default_config_path,
+};
+pub use diagnostics::{AttemptLog, FetchAttempt};
+pub use engine::{Clock, Effect, EngineInput, PollingEngine, SystemClock};
+pub use error::{ErrorIndicator, FetchError, NetworkError, NetworkErrorKind};
 pub use events::{PollAction, SystemEvent};
-pub use poller::{AdaptivePoller, PollerConfig, TemperatureState, UsageMetrics};
-pub use state::{UsageData, UsagePeriod};
+pub use forecast::Forecast;
+pub use history::{FetchRecord, HistoryConfig, HistoryStore};
+pub use http::{CookieJar, HttpConfig};
+pub use icon::IconConfig;
+pub use jitter::{Jitter, JitterKind, Rng};
+pub use notify::{Notification, NotificationConfig, NotificationTracker, Notifier, Urgency};
+pub use poller::{
+    AdaptivePoller, MetricValue, Momentum, Permille, PollerConfig, TemperatureState, Transition,
+    TransitionKind, UsageMetrics,
+};
+pub use provider::{MetricDescriptor, MetricRole, UsageProvider, UsageSnapshot};
+pub use retry::{RetryConfig, RetryState};
+pub use schema::{DriftRecorder, SchemaReport, parse_usage};
+pub use simulate::{SimulateArgs, SimulationReport, TraceSample, parse_trace, simulate};
+pub use state::{AppState, UsageData, UsagePeriod, UserPause};
+pub use strategy::{PollingStrategy, StrategyInput, StrategyKind};
 
 // Re-export for testing
 #[doc(hidden)]
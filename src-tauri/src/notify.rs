@@ -0,0 +1,592 @@
+use crate::error::FetchError;
+use crate::forecast::format_reset_time;
+use crate::poller::{Transition, TransitionKind};
+use crate::provider::{MetricDescriptor, UsageSnapshot};
+use chrono::Local;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::future::Future;
+
+/// Configuration for desktop notifications
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotificationConfig {
+    pub enabled: bool,
+    /// Utilization percentages that trigger a notification when crossed
+    pub thresholds: Vec<u8>,
+    /// Percentage points usage must drop below a threshold before it can fire again
+    pub hysteresis: f64,
+    /// Consecutive auth failures before notifying
+    pub auth_failure_threshold: u32,
+    /// Notify when a window resets after crossing a threshold
+    pub notify_on_reset: bool,
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            thresholds: vec![80, 90, 95],
+            hysteresis: 3.0,
+            auth_failure_threshold: 3,
+            notify_on_reset: true,
+        }
+    }
+}
+
+/// Notification urgency levels from the freedesktop specification
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Urgency {
+    Low = 0,
+    Normal = 1,
+    Critical = 2,
+}
+
+/// A notification ready to be shown to the user
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notification {
+    pub summary: String,
+    pub body: String,
+    pub urgency: Urgency,
+}
+
+/// Something that can display notifications
+pub trait Notifier: Send + Sync + 'static {
+    fn notify(
+        &self,
+        notification: &Notification,
+    ) -> impl Future<Output = Result<(), String>> + Send;
+}
+
+/// Last observed state of a single metric
+#[derive(Debug, Clone)]
+struct MetricState {
+    utilization: f64,
+    /// Number of thresholds (in ascending order) currently crossed
+    level: usize,
+}
+
+/// Turns fetch results into notifications, suppressing repeats
+///
+/// A threshold fires once when crossed and is re-armed only after usage drops
+/// `hysteresis` points below it, so a metric hovering around a threshold
+/// doesn't notify on every poll.
+pub struct NotificationTracker {
+    config: NotificationConfig,
+    thresholds: Vec<u8>,
+    metrics: HashMap<String, MetricState>,
+    auth_failures: u32,
+    auth_notified: bool,
+}
+
+impl NotificationTracker {
+    pub fn new(config: NotificationConfig) -> Self {
+        let mut tracker = Self {
+            config: NotificationConfig::default(),
+            thresholds: Vec::new(),
+            metrics: HashMap::new(),
+            auth_failures: 0,
+            auth_notified: false,
+        };
+        tracker.update_config(config);
+        tracker
+    }
+
+    /// Apply a new configuration, keeping per-metric state where possible
+    pub fn update_config(&mut self, config: NotificationConfig) {
+        let mut thresholds = config.thresholds.clone();
+        thresholds.sort_unstable();
+        thresholds.dedup();
+
+        // Recompute levels against the new thresholds without firing
+        for state in self.metrics.values_mut() {
+            state.level = thresholds
+                .iter()
+                .filter(|&&t| state.utilization >= f64::from(t))
+                .count();
+        }
+
+        self.thresholds = thresholds;
+        self.config = config;
+    }
+
+    fn level_for(&self, utilization: f64) -> usize {
+        self.thresholds
+            .iter()
+            .filter(|&&t| utilization >= f64::from(t))
+            .count()
+    }
+
+    /// Record a successful fetch, returning notifications to show
+    ///
+    /// `transitions` are the poller's classification of how each metric moved
+    /// since the previous fetch; resets re-arm every threshold.
+    pub fn on_success(
+        &mut self,
+        provider_name: &str,
+        descriptors: &[MetricDescriptor],
+        snapshot: &UsageSnapshot,
+        transitions: &[Transition],
+    ) -> Vec<Notification> {
+        self.auth_failures = 0;
+        self.auth_notified = false;
+
+        let mut notifications = Vec::new();
+        for descriptor in descriptors {
+            let Some(period) = snapshot.get(descriptor.id) else {
+                continue;
+            };
+            let utilization = period.utilization;
+            let target = self.level_for(utilization);
+
+            let Some(previous) = self.metrics.get(descriptor.id).cloned() else {
+                // First observation only establishes a baseline
+                self.metrics.insert(
+                    descriptor.id.to_string(),
+                    MetricState {
+                        utilization,
+                        level: target,
+                    },
+                );
+                continue;
+            };
+
+            let reset = transitions
+                .iter()
+                .any(|t| t.metric == descriptor.id && t.kind == TransitionKind::Reset);
+
+            let level = if reset {
+                if self.config.notify_on_reset && previous.level > 0 {
+                    notifications.push(Notification {
+                        summary: format!("{} {} window reset", provider_name, descriptor.label),
+                        body: format!(
+                            "{} window reset, you're back to {}%.",
+                            descriptor.label,
+                            utilization.round()
+                        ),
+                        urgency: Urgency::Low,
+                    });
+                }
+                target
+            } else if target > previous.level {
+                let threshold = self.thresholds[target - 1];
+                notifications.push(Notification {
+                    summary: format!("{} usage at {}%", provider_name, utilization.round()),
+                    body: match period.resets_at {
+                        Some(resets_at) => format!(
+                            "{} usage crossed {}% (resets {}).",
+                            descriptor.label,
+                            threshold,
+                            format_reset_time(resets_at, &Local)
+                        ),
+                        None => format!("{} usage crossed {}%.", descriptor.label, threshold),
+                    },
+                    urgency: if target == self.thresholds.len() {
+                        Urgency::Critical
+                    } else {
+                        Urgency::Normal
+                    },
+                });
+                target
+            } else {
+                // Only re-arm thresholds usage has clearly dropped below
+                let mut level = previous.level;
+                while level > 0
+                    && utilization < f64::from(self.thresholds[level - 1]) - self.config.hysteresis
+                {
+                    level -= 1;
+                }
+                level
+            };
+
+            self.metrics.insert(
+                descriptor.id.to_string(),
+                MetricState { utilization, level },
+            );
+        }
+
+        notifications
+    }
+
+    /// Record a failed fetch, returning a notification once auth keeps failing
+    pub fn on_error(&mut self, provider_name: &str, error: &FetchError) -> Option<Notification> {
+        if !matches!(error, FetchError::Auth { .. }) {
+            return None;
+        }
+
+        self.auth_failures += 1;
+        if self.auth_notified || self.auth_failures < self.config.auth_failure_threshold {
+            return None;
+        }
+
+        self.auth_notified = true;
+        Some(Notification {
+            summary: format!("{} authentication failing", provider_name),
+            body: format!(
+                "The last {} fetches failed: {}. Update the credentials in the config file.",
+                self.auth_failures, error
+            ),
+            urgency: Urgency::Critical,
+        })
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub mod platform {
+    use super::{Notification, Notifier};
+    use std::collections::HashMap;
+    use zbus::zvariant::Value;
+    use zbus::{Connection, proxy};
+
+    const APP_NAME: &str = "Usage Indicator";
+    /// Let the notification server pick the expiry
+    const DEFAULT_TIMEOUT: i32 = -1;
+
+    #[proxy(
+        interface = "org.freedesktop.Notifications",
+        default_service = "org.freedesktop.Notifications",
+        default_path = "/org/freedesktop/Notifications"
+    )]
+    trait Notifications {
+        #[allow(clippy::too_many_arguments)]
+        fn notify(
+            &self,
+            app_name: &str,
+            replaces_id: u32,
+            app_icon: &str,
+            summary: &str,
+            body: &str,
+            actions: &[&str],
+            hints: HashMap<&str, Value<'_>>,
+            expire_timeout: i32,
+        ) -> zbus::Result<u32>;
+    }
+
+    /// Sends notifications through the freedesktop notification D-Bus interface
+    #[derive(Clone)]
+    pub struct DesktopNotifier {
+        connection: Connection,
+    }
+
+    impl DesktopNotifier {
+        /// Connect to the session bus
+        pub async fn connect() -> Result<Self, String> {
+            let connection = Connection::session()
+                .await
+                .map_err(|e| format!("Failed to connect to D-Bus session bus: {}", e))?;
+            Ok(Self::with_connection(connection))
+        }
+
+        /// Use an existing connection (e.g. a peer-to-peer stand-in in tests)
+        pub fn with_connection(connection: Connection) -> Self {
+            Self { connection }
+        }
+    }
+
+    impl Notifier for DesktopNotifier {
+        async fn notify(&self, notification: &Notification) -> Result<(), String> {
+            let proxy = NotificationsProxy::builder(&self.connection)
+                .cache_properties(zbus::proxy::CacheProperties::No)
+                .build()
+                .await
+                .map_err(|e| format!("Failed to create notification proxy: {}", e))?;
+
+            let hints = HashMap::from([("urgency", Value::U8(notification.urgency as u8))]);
+            proxy
+                .notify(
+                    APP_NAME,
+                    0,
+                    "",
+                    &notification.summary,
+                    &notification.body,
+                    &[],
+                    hints,
+                    DEFAULT_TIMEOUT,
+                )
+                .await
+                .map(|_| ())
+                .map_err(|e| format!("Failed to send notification: {}", e))
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub mod platform {
+    use super::{Notification, Notifier};
+    use tracing::info;
+
+    /// Placeholder for platforms without notification support yet
+    /// Logs notifications instead of displaying them
+    #[derive(Clone)]
+    pub struct DesktopNotifier;
+
+    impl DesktopNotifier {
+        pub async fn connect() -> Result<Self, String> {
+            Ok(Self)
+        }
+    }
+
+    impl Notifier for DesktopNotifier {
+        async fn notify(&self, notification: &Notification) -> Result<(), String> {
+            info!(summary = %notification.summary, "Notification: {}", notification.body);
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::NetworkErrorKind;
+    use crate::poller::Permille;
+    use crate::provider::MetricRole;
+    use crate::state::UsagePeriod;
+    use assert2::{assert, let_assert};
+    use std::collections::BTreeMap;
+
+    const METRICS: &[MetricDescriptor] = &[MetricDescriptor {
+        id: "five_hour",
+        label: "5-hour",
+        role: MetricRole::ShortWindow,
+    }];
+
+    fn snapshot(utilization: f64, resets_at: Option<&str>) -> UsageSnapshot {
+        UsageSnapshot {
+            periods: BTreeMap::from([(
+                "five_hour".to_string(),
+                UsagePeriod {
+                    utilization,
+                    resets_at: resets_at.map(|s| s.parse().unwrap()),
+                },
+            )]),
+            http_status: None,
+        }
+    }
+
+    /// Feed a sequence of utilizations, returning the summaries of each poll's notifications
+    fn run(tracker: &mut NotificationTracker, values: &[f64]) -> Vec<Vec<String>> {
+        values
+            .iter()
+            .map(|&u| {
+                tracker
+                    .on_success(
+                        "Claude",
+                        METRICS,
+                        &snapshot(u, Some("2025-11-29T00:00:00Z")),
+                        &[],
+                    )
+                    .into_iter()
+                    .map(|n| n.summary)
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_threshold_fires_once_with_hysteresis() {
+        let mut tracker = NotificationTracker::new(NotificationConfig::default());
+
+        let fired = run(&mut tracker, &[70.0, 81.0, 79.0, 80.0, 78.0, 76.0, 80.0]);
+
+        assert!(fired[0].is_empty()); // baseline
+        assert!(fired[1] == vec!["Claude usage at 81%"]);
+        // Hovering within the hysteresis band stays quiet
+        assert!(fired[2].is_empty());
+        assert!(fired[3].is_empty());
+        assert!(fired[4].is_empty());
+        // Dropped below 77%, so crossing 80% again fires
+        assert!(fired[5].is_empty());
+        assert!(fired[6] == vec!["Claude usage at 80%"]);
+    }
+
+    #[test]
+    fn test_jump_over_several_thresholds_fires_once() {
+        let mut tracker = NotificationTracker::new(NotificationConfig::default());
+        tracker.on_success("Claude", METRICS, &snapshot(50.0, None), &[]);
+
+        let notifications = tracker.on_success("Claude", METRICS, &snapshot(96.0, None), &[]);
+
+        let_assert!([notification] = notifications.as_slice());
+        assert!(notification.body == "5-hour usage crossed 95%.");
+        assert!(notification.urgency == Urgency::Critical);
+    }
+
+    fn reset(from: u8, to: u8) -> Transition {
+        Transition {
+            metric: "five_hour".to_string(),
+            kind: TransitionKind::Reset,
+            from: Permille::from_whole(from),
+            to: Permille::from_whole(to),
+        }
+    }
+
+    #[test]
+    fn test_window_reset_notifies_once() {
+        let mut tracker = NotificationTracker::new(NotificationConfig::default());
+        let before = Some("2025-11-29T00:00:00Z");
+        tracker.on_success("Claude", METRICS, &snapshot(85.0, before), &[]);
+        tracker.on_success("Claude", METRICS, &snapshot(91.0, before), &[]);
+
+        let after = Some("2025-11-29T05:00:00Z");
+        let notifications =
+            tracker.on_success("Claude", METRICS, &snapshot(0.0, after), &[reset(91, 0)]);
+        let_assert!([notification] = notifications.as_slice());
+        assert!(notification.summary == "Claude 5-hour window reset");
+        assert!(notification.body == "5-hour window reset, you're back to 0%.");
+
+        assert!(
+            tracker
+                .on_success("Claude", METRICS, &snapshot(2.0, after), &[])
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_decay_is_not_a_reset() {
+        let mut tracker = NotificationTracker::new(NotificationConfig::default());
+        let resets_at = Some("2025-11-29T00:00:00Z");
+        tracker.on_success("Claude", METRICS, &snapshot(91.0, resets_at), &[]);
+
+        let decay = Transition {
+            kind: TransitionKind::Decay,
+            ..reset(91, 70)
+        };
+        assert!(
+            tracker
+                .on_success("Claude", METRICS, &snapshot(70.0, resets_at), &[decay])
+                .is_empty()
+        );
+        // Thresholds re-armed on the way down, so climbing back notifies again
+        let fired = run(&mut tracker, &[81.0]);
+        assert!(fired[0] == vec!["Claude usage at 81%"]);
+    }
+
+    #[test]
+    fn test_quiet_window_reset_is_not_notified() {
+        let mut tracker = NotificationTracker::new(NotificationConfig::default());
+        tracker.on_success(
+            "Claude",
+            METRICS,
+            &snapshot(30.0, Some("2025-11-29T00:00:00Z")),
+            &[],
+        );
+
+        assert!(
+            tracker
+                .on_success("Claude", METRICS, &snapshot(0.0, None), &[reset(30, 0)])
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_persistent_auth_failure_notifies_once() {
+        let mut tracker = NotificationTracker::new(NotificationConfig::default());
+        let auth = FetchError::auth("Session expired");
+
+        assert!(tracker.on_error("Claude", &auth).is_none());
+        // Other errors neither count nor reset the streak
+        assert!(
+            tracker
+                .on_error(
+                    "Claude",
+                    &FetchError::network(NetworkErrorKind::Timeout, "down")
+                )
+                .is_none()
+        );
+        assert!(tracker.on_error("Claude", &auth).is_none());
+        let_assert!(Some(notification) = tracker.on_error("Claude", &auth));
+        assert!(notification.urgency == Urgency::Critical);
+        assert!(tracker.on_error("Claude", &auth).is_none());
+
+        // A success re-arms the auth notification
+        tracker.on_success("Claude", METRICS, &snapshot(10.0, None), &[]);
+        for _ in 0..2 {
+            assert!(tracker.on_error("Claude", &auth).is_none());
+        }
+        assert!(tracker.on_error("Claude", &auth).is_some());
+    }
+
+    #[cfg(target_os = "linux")]
+    mod dbus {
+        use super::super::platform::DesktopNotifier;
+        use super::super::{Notification, Notifier, Urgency};
+        use assert2::{assert, let_assert};
+        use std::collections::HashMap;
+        use std::os::unix::net::UnixStream;
+        use std::sync::{Arc, Mutex};
+        use zbus::connection::Builder;
+        use zbus::zvariant::OwnedValue;
+        use zbus::{Guid, interface};
+
+        /// (app name, summary, body, urgency)
+        type Received = (String, String, String, u8);
+
+        /// Stand-in for a notification daemon that records what it receives
+        #[derive(Default, Clone)]
+        struct RecordingServer {
+            received: Arc<Mutex<Vec<Received>>>,
+        }
+
+        #[interface(name = "org.freedesktop.Notifications")]
+        impl RecordingServer {
+            #[allow(clippy::too_many_arguments)]
+            fn notify(
+                &self,
+                app_name: String,
+                _replaces_id: u32,
+                _app_icon: String,
+                summary: String,
+                body: String,
+                _actions: Vec<String>,
+                hints: HashMap<String, OwnedValue>,
+                _expire_timeout: i32,
+            ) -> u32 {
+                let urgency = hints
+                    .get("urgency")
+                    .and_then(|v| u8::try_from(v).ok())
+                    .unwrap_or(u8::MAX);
+                let mut received = self.received.lock().unwrap();
+                received.push((app_name, summary, body, urgency));
+                received.len() as u32
+            }
+        }
+
+        #[tokio::test]
+        async fn test_desktop_notifier_sends_over_dbus() {
+            let server = RecordingServer::default();
+            let (server_stream, client_stream) = UnixStream::pair().unwrap();
+
+            let_assert!(
+                Ok(server_builder) = Builder::unix_stream(server_stream).server(Guid::generate())
+            );
+            let server_builder = server_builder
+                .p2p()
+                .serve_at("/org/freedesktop/Notifications", server.clone())
+                .unwrap();
+            let (server_conn, client_conn) = tokio::join!(
+                server_builder.build(),
+                Builder::unix_stream(client_stream).p2p().build()
+            );
+            let _server_conn = server_conn.unwrap();
+
+            let notifier = DesktopNotifier::with_connection(client_conn.unwrap());
+            let notification = Notification {
+                summary: "Claude usage at 90%".to_string(),
+                body: "Weekly usage crossed 90%.".to_string(),
+                urgency: Urgency::Critical,
+            };
+            assert!(notifier.notify(&notification).await.is_ok());
+
+            let received = server.received.lock().unwrap().clone();
+            assert!(
+                received
+                    == vec![(
+                        "Usage Indicator".to_string(),
+                        "Claude usage at 90%".to_string(),
+                        "Weekly usage crossed 90%.".to_string(),
+                        2
+                    )]
+            );
+        }
+    }
+}
@@ -1,64 +1,261 @@
-use std::collections::BTreeMap;
+use crate::jitter::{Jitter, JitterKind};
+use crate::provider::MetricRole;
+use crate::strategy::{self, PollingStrategy, StrategyInput, StrategyKind};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
 use std::time::{Duration, Instant};
 use tracing::{debug, info};
 
-/// Usage metrics with 1% resolution
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub struct UsageMetrics {
-    five_hour_pct: u8,
-    weekly_pct: u8,
-}
+/// Usage in tenths of a percent, so small steady usage still registers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Permille(u16);
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum UsageMetricsError {
-    FiveHourOutOfRange(u8),
-    WeeklyOutOfRange(u8),
+impl Permille {
+    pub const ZERO: Self = Self(0);
+    /// 100%
+    pub const FULL: Self = Self(1000);
+
+    pub const fn from_tenths(tenths: u16) -> Self {
+        Self(tenths)
+    }
+
+    pub const fn from_whole(pct: u8) -> Self {
+        Self(pct as u16 * 10)
+    }
+
+    /// Round a percentage to the nearest tenth (negative values become 0)
+    pub fn from_pct(pct: f64) -> Self {
+        Self((pct * 10.0).round() as u16)
+    }
+
+    pub fn tenths(self) -> u16 {
+        self.0
+    }
+
+    pub fn as_pct(self) -> f64 {
+        f64::from(self.0) / 10.0
+    }
+
+    /// Above 100%, which the API reports while in overage
+    pub fn is_over_limit(self) -> bool {
+        self > Self::FULL
+    }
+
+    /// Rounded to a whole percent
+    pub fn whole_pct(self) -> u8 {
+        (self.0.saturating_add(5) / 10).min(u16::from(u8::MAX)) as u8
+    }
+
+    pub fn saturating_add(self, other: Self) -> Self {
+        Self(self.0.saturating_add(other.0))
+    }
+
+    /// Difference, or zero when `other` is larger
+    pub fn saturating_sub(self, other: Self) -> Self {
+        Self(self.0.saturating_sub(other.0))
+    }
 }
 
-impl std::fmt::Display for UsageMetricsError {
+impl std::fmt::Display for Permille {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::FiveHourOutOfRange(val) => {
-                write!(f, "five_hour_pct {} is out of range 0-100", val)
-            }
-            Self::WeeklyOutOfRange(val) => write!(f, "weekly_pct {} is out of range 0-100", val),
-        }
+        write!(f, "{}", self.as_pct())
     }
 }
 
-impl std::error::Error for UsageMetricsError {}
+/// Usage of a single metric
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MetricValue {
+    pub usage: Permille,
+    pub role: MetricRole,
+    /// When the metric's window resets, used to tell resets from other drops
+    pub resets_at: Option<DateTime<Utc>>,
+}
+
+/// Usage metrics with 0.1% resolution, keyed by metric id
+///
+/// Values above 100% are kept as reported, since the API goes over the
+/// limit while in overage.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UsageMetrics {
+    values: BTreeMap<String, MetricValue>,
+}
 
 impl UsageMetrics {
-    /// Try to create new UsageMetrics, returning an error if values are out of range (0-100)
-    pub fn try_new(five_hour_pct: u8, weekly_pct: u8) -> Result<Self, UsageMetricsError> {
-        if five_hour_pct > 100 {
-            return Err(UsageMetricsError::FiveHourOutOfRange(five_hour_pct));
-        }
-        if weekly_pct > 100 {
-            return Err(UsageMetricsError::WeeklyOutOfRange(weekly_pct));
+    /// Create metrics for the usual 5-hour (`five_hour`) and weekly
+    /// (`seven_day`) windows
+    pub fn new(five_hour_pct: u8, weekly_pct: u8) -> Self {
+        let mut metrics = Self::default();
+        metrics.insert(
+            "five_hour",
+            MetricRole::ShortWindow,
+            Permille::from_whole(five_hour_pct),
+        );
+        metrics.insert(
+            "seven_day",
+            MetricRole::LongWindow,
+            Permille::from_whole(weekly_pct),
+        );
+        metrics
+    }
+
+    /// Add or replace a metric
+    pub fn insert(&mut self, id: &str, role: MetricRole, usage: Permille) {
+        self.values.insert(
+            id.to_string(),
+            MetricValue {
+                usage,
+                role,
+                resets_at: None,
+            },
+        );
+    }
+
+    /// Whether any metric is above 100%
+    pub fn is_over_limit(&self) -> bool {
+        self.values.values().any(|v| v.usage.is_over_limit())
+    }
+
+    /// Attach the reset time of an inserted metric
+    pub fn set_resets_at(&mut self, id: &str, resets_at: Option<DateTime<Utc>>) {
+        if let Some(value) = self.values.get_mut(id) {
+            value.resets_at = resets_at;
         }
+    }
 
-        Ok(Self {
-            five_hour_pct,
-            weekly_pct,
-        })
+    pub fn get(&self, id: &str) -> Option<Permille> {
+        self.values.get(id).map(|value| value.usage)
     }
 
-    /// Create new UsageMetrics, panicking if values are out of range (0-100)
-    pub fn new(five_hour_pct: u8, weekly_pct: u8) -> Self {
-        Self::try_new(five_hour_pct, weekly_pct)
-            .expect("UsageMetrics values must be in range 0-100")
+    pub fn iter(&self) -> impl Iterator<Item = (&str, MetricValue)> {
+        self.values.iter().map(|(id, value)| (id.as_str(), *value))
+    }
+
+    /// The first metric (by id) with the given role
+    pub fn by_role(&self, role: MetricRole) -> Option<(&str, Permille)> {
+        self.iter()
+            .find(|(_, value)| value.role == role)
+            .map(|(id, value)| (id, value.usage))
     }
 
-    pub fn five_hour_pct(&self) -> u8 {
-        self.five_hour_pct
+    /// Short window usage (0 when the provider has no short window metric)
+    pub fn five_hour(&self) -> Permille {
+        self.by_role(MetricRole::ShortWindow)
+            .map_or(Permille::ZERO, |(_, usage)| usage)
     }
 
-    pub fn weekly_pct(&self) -> u8 {
-        self.weekly_pct
+    /// Long window usage (0 when the provider has no long window metric)
+    pub fn weekly(&self) -> Permille {
+        self.by_role(MetricRole::LongWindow)
+            .map_or(Permille::ZERO, |(_, usage)| usage)
+    }
+
+    /// How each metric moved since `previous`
+    ///
+    /// A drop is a hard reset when the reset time changed. Without reset times,
+    /// a drop to half or less is taken as a reset and smaller drops as the
+    /// rolling window decaying.
+    fn transitions_since(&self, previous: &UsageMetrics) -> Vec<Transition> {
+        self.values
+            .iter()
+            .filter_map(|(id, after)| {
+                let before = previous.values.get(id)?;
+                let kind = match after.usage.cmp(&before.usage) {
+                    Ordering::Equal => return None,
+                    Ordering::Greater => TransitionKind::Increase,
+                    Ordering::Less => {
+                        let reset = match (before.resets_at, after.resets_at) {
+                            (Some(before), Some(after)) => before != after,
+                            _ => after.usage.tenths() <= before.usage.tenths() / 2,
+                        };
+                        if reset {
+                            TransitionKind::Reset
+                        } else {
+                            TransitionKind::Decay
+                        }
+                    }
+                };
+                Some(Transition {
+                    metric: id.clone(),
+                    kind,
+                    from: before.usage,
+                    to: after.usage,
+                })
+            })
+            .collect()
+    }
+
+    /// Earliest reset time after `now`
+    pub fn next_reset(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.values
+            .values()
+            .filter_map(|value| value.resets_at)
+            .filter(|resets_at| *resets_at > now)
+            .min()
+    }
+}
+
+/// Increases observed over a window of tracked history
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Momentum {
+    /// Increase per metric id
+    pub metrics: BTreeMap<String, Permille>,
+    /// Time actually covered by the samples (zero with fewer than two samples)
+    pub span: Duration,
+}
+
+impl Momentum {
+    /// Increase of one metric (0 when it wasn't tracked)
+    pub fn get(&self, id: &str) -> Permille {
+        self.metrics.get(id).copied().unwrap_or_default()
     }
 }
 
+/// How a metric moved between two consecutive samples
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransitionKind {
+    /// Usage went up
+    Increase,
+    /// Usage went down as old usage aged out of a rolling window
+    Decay,
+    /// The window reset and usage started over
+    Reset,
+}
+
+impl TransitionKind {
+    /// Stable lowercase name, used for persistence
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TransitionKind::Increase => "increase",
+            TransitionKind::Decay => "decay",
+            TransitionKind::Reset => "reset",
+        }
+    }
+}
+
+impl std::str::FromStr for TransitionKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "increase" => Ok(TransitionKind::Increase),
+            "decay" => Ok(TransitionKind::Decay),
+            "reset" => Ok(TransitionKind::Reset),
+            other => Err(format!("unknown transition kind '{}'", other)),
+        }
+    }
+}
+
+/// A change of one metric between two consecutive samples
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transition {
+    pub metric: String,
+    pub kind: TransitionKind,
+    pub from: Permille,
+    pub to: Permille,
+}
+
 /// Temperature-based activity states
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TemperatureState {
@@ -74,9 +271,41 @@ pub enum TemperatureState {
     Blazing,
 }
 
+impl TemperatureState {
+    /// Stable lowercase name, used for persistence
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TemperatureState::Cold => "cold",
+            TemperatureState::Cool => "cool",
+            TemperatureState::Warm => "warm",
+            TemperatureState::Hot => "hot",
+            TemperatureState::Blazing => "blazing",
+        }
+    }
+}
+
+impl std::str::FromStr for TemperatureState {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "cold" => Ok(TemperatureState::Cold),
+            "cool" => Ok(TemperatureState::Cool),
+            "warm" => Ok(TemperatureState::Warm),
+            "hot" => Ok(TemperatureState::Hot),
+            "blazing" => Ok(TemperatureState::Blazing),
+            other => Err(format!("unknown temperature state '{}'", other)),
+        }
+    }
+}
+
 /// Configuration for adaptive polling behavior
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct PollerConfig {
+    /// How the interval is chosen after each fetch
+    pub strategy: StrategyKind,
+
     // Interval bounds
     pub min_interval_secs: u64,
     pub max_interval_secs: u64,
@@ -87,20 +316,35 @@ pub struct PollerConfig {
     pub context_window_secs: u64,
     pub idle_to_cold_secs: u64,
 
-    // Change thresholds (percentage points)
-    pub five_hour_sustained_threshold: u8,
-    pub weekly_sustained_threshold: u8,
-    pub five_hour_recent_threshold: u8,
+    /// How long after a window resets to fetch, so the reset shows up promptly
+    pub reset_grace_secs: u64,
+
+    // Change thresholds (percentage points, down to a tenth)
+    pub five_hour_sustained_threshold: f64,
+    pub weekly_sustained_threshold: f64,
+    pub five_hour_recent_threshold: f64,
 
     // AIMD multipliers
     pub warm_multiplier: f64,
     pub hot_multiplier: f64,
     pub blazing_multiplier: f64,
+
+    /// Interval of the `fixed` strategy
+    pub fixed_interval_secs: u64,
+    /// Idle time over which the `idle_decay` strategy doubles its interval
+    pub idle_doubling_secs: u64,
+    /// Fetches per day of the `daily_budget` strategy
+    pub daily_requests: u32,
+
+    /// Randomness that shortens intervals so clients don't poll in lockstep
+    pub jitter: JitterKind,
 }
 
 impl Default for PollerConfig {
     fn default() -> Self {
         Self {
+            strategy: StrategyKind::Adaptive,
+
             min_interval_secs: 180,     // 3 minutes
             max_interval_secs: 5400,    // 90 minutes
             additive_increase_secs: 90, // 1.5 minutes
@@ -109,38 +353,22 @@ impl Default for PollerConfig {
             context_window_secs: 3600, // 1 hour
             idle_to_cold_secs: 1800,   // 30 minutes
 
-            five_hour_sustained_threshold: 4,
-            weekly_sustained_threshold: 2,
-            five_hour_recent_threshold: 2,
+            reset_grace_secs: 30,
+
+            five_hour_sustained_threshold: 4.0,
+            weekly_sustained_threshold: 2.0,
+            five_hour_recent_threshold: 2.0,
 
             warm_multiplier: 0.7,
             hot_multiplier: 0.4,
             blazing_multiplier: 0.25,
-        }
-    }
-}
 
-macro_rules! env_or_default {
-    ($config:expr, $field:ident, $env_var:expr) => {
-        if let Ok(val) = std::env::var($env_var) {
-            if let Ok(parsed) = val.parse() {
-                $config.$field = parsed;
-            }
-        }
-    };
-}
-
-impl PollerConfig {
-    pub fn from_env() -> Self {
-        let mut config = Self::default();
-
-        env_or_default!(config, min_interval_secs, "POLL_MIN_INTERVAL_SECS");
-        env_or_default!(config, max_interval_secs, "POLL_MAX_INTERVAL_SECS");
-        env_or_default!(config, recency_window_secs, "POLL_RECENCY_WINDOW_SECS");
-        env_or_default!(config, context_window_secs, "POLL_CONTEXT_WINDOW_SECS");
-        env_or_default!(config, idle_to_cold_secs, "POLL_IDLE_TO_COLD_SECS");
+            fixed_interval_secs: 900, // 15 minutes
+            idle_doubling_secs: 1800, // 30 minutes
+            daily_requests: 96,       // every 15 minutes
 
-        config
+            jitter: JitterKind::None,
+        }
     }
 }
 
@@ -148,6 +376,8 @@ impl PollerConfig {
 struct TimeWindowedTracker {
     /// Time-ordered history of usage samples
     history: BTreeMap<Instant, UsageMetrics>,
+    /// Classified changes, keyed by (sample time, metric id)
+    transitions: BTreeMap<(Instant, String), TransitionKind>,
     max_history_duration: Duration,
 }
 
@@ -155,41 +385,75 @@ impl TimeWindowedTracker {
     fn new(max_duration: Duration) -> Self {
         Self {
             history: BTreeMap::new(),
+            transitions: BTreeMap::new(),
             max_history_duration: max_duration,
         }
     }
 
-    fn record_sample(&mut self, metrics: UsageMetrics, now: Instant) {
+    fn set_max_history_duration(&mut self, max_duration: Duration) {
+        self.max_history_duration = max_duration;
+    }
+
+    /// Add a sample, returning how each metric moved since the previous one
+    fn record_sample(&mut self, metrics: UsageMetrics, now: Instant) -> Vec<Transition> {
+        let transitions = match self.history.range(..now).next_back() {
+            Some((_, previous)) => metrics.transitions_since(previous),
+            None => Vec::new(),
+        };
+        for transition in &transitions {
+            if transition.kind == TransitionKind::Reset {
+                info!(
+                    metric = %transition.metric,
+                    from_pct = transition.from.as_pct(),
+                    to_pct = transition.to.as_pct(),
+                    "Usage window reset"
+                );
+            }
+            self.transitions
+                .insert((now, transition.metric.clone()), transition.kind);
+        }
         self.history.insert(now, metrics);
 
         let cutoff = now.checked_sub(self.max_history_duration).unwrap_or(now);
         self.history = self.history.split_off(&cutoff);
+        self.transitions.retain(|(at, _), _| *at >= cutoff);
+        transitions
     }
 
-    fn calculate_momentum<F>(
-        &self,
+    /// How `id` moved into the sample taken at `at`
+    fn transition(&self, at: Instant, id: &str) -> Option<TransitionKind> {
+        self.transitions.get(&(at, id.to_string())).copied()
+    }
+
+    /// Sum of increases of the metric picked by `select` over `window`
+    ///
+    /// Only steps classified as increases count, so resets and decay never
+    /// read as usage in either direction.
+    fn calculate_momentum<'a, F>(
+        &'a self,
         window: Duration,
         now: Instant,
-        extractor: F,
-        max_change: Option<u8>,
-    ) -> u8
+        select: F,
+        max_change: Option<Permille>,
+    ) -> Permille
     where
-        F: Fn(&UsageMetrics) -> u8,
+        F: Fn(&'a UsageMetrics) -> Option<(&'a str, Permille)>,
     {
         let cutoff = now.checked_sub(window).unwrap_or(now);
-        let samples: Vec<u8> = self
+        let samples: Vec<(Instant, &str, Permille)> = self
             .history
             .range(cutoff..)
-            .map(|(_, metrics)| extractor(metrics))
+            .filter_map(|(at, metrics)| select(metrics).map(|(id, usage)| (*at, id, usage)))
             .collect();
 
         if samples.len() < 2 {
-            return 0;
+            return Permille::ZERO;
         }
 
-        samples.windows(2).fold(0u8, |total, w| {
-            if w[1] > w[0] {
-                let change = w[1] - w[0];
+        samples.windows(2).fold(Permille::ZERO, |total, w| {
+            let ((_, _, before), (at, id, after)) = (w[0], w[1]);
+            if after > before && self.transition(at, id) == Some(TransitionKind::Increase) {
+                let change = after.saturating_sub(before);
                 if max_change.is_none_or(|max| change <= max) {
                     total.saturating_add(change)
                 } else {
@@ -201,12 +465,72 @@ impl TimeWindowedTracker {
         })
     }
 
-    fn calculate_five_hour_momentum(&self, window: Duration, now: Instant) -> u8 {
-        self.calculate_momentum(window, now, |m| m.five_hour_pct(), Some(10))
+    /// Largest plausible single-step increase for a metric role
+    ///
+    /// Short windows reset often, so bigger jumps are treated as noise.
+    fn max_change(role: MetricRole) -> Option<Permille> {
+        match role {
+            MetricRole::ShortWindow => Some(Permille::from_whole(10)),
+            MetricRole::LongWindow | MetricRole::Auxiliary => None,
+        }
+    }
+
+    fn calculate_five_hour_momentum(&self, window: Duration, now: Instant) -> Permille {
+        self.calculate_momentum(
+            window,
+            now,
+            |m| m.by_role(MetricRole::ShortWindow),
+            Self::max_change(MetricRole::ShortWindow),
+        )
+    }
+
+    fn calculate_weekly_momentum(&self, window: Duration, now: Instant) -> Permille {
+        self.calculate_momentum(
+            window,
+            now,
+            |m| m.by_role(MetricRole::LongWindow),
+            Self::max_change(MetricRole::LongWindow),
+        )
+    }
+
+    /// Momentum of a single metric, tracked independently of its role
+    fn calculate_metric_momentum<'a>(
+        &'a self,
+        id: &'a str,
+        window: Duration,
+        now: Instant,
+    ) -> Permille {
+        let role = self
+            .history
+            .values()
+            .rev()
+            .find_map(|metrics| metrics.values.get(id))
+            .map_or(MetricRole::Auxiliary, |value| value.role);
+        self.calculate_momentum(
+            window,
+            now,
+            |m| m.get(id).map(|usage| (id, usage)),
+            Self::max_change(role),
+        )
+    }
+
+    /// Ids of every metric seen inside `window`
+    fn metric_ids(&self, window: Duration, now: Instant) -> BTreeSet<&str> {
+        let cutoff = now.checked_sub(window).unwrap_or(now);
+        self.history
+            .range(cutoff..)
+            .flat_map(|(_, metrics)| metrics.values.keys().map(String::as_str))
+            .collect()
     }
 
-    fn calculate_weekly_momentum(&self, window: Duration, now: Instant) -> u8 {
-        self.calculate_momentum(window, now, |m| m.weekly_pct(), None)
+    /// Time between the oldest sample inside `window` and `now`
+    fn covered_span(&self, window: Duration, now: Instant) -> Duration {
+        let cutoff = now.checked_sub(window).unwrap_or(now);
+        let mut samples = self.history.range(cutoff..);
+        match (samples.next(), samples.next()) {
+            (Some((oldest, _)), Some(_)) => now.saturating_duration_since(*oldest),
+            _ => Duration::ZERO,
+        }
     }
 
     fn time_since_any_change(&self, now: Instant) -> Duration {
@@ -214,21 +538,22 @@ impl TimeWindowedTracker {
             return Duration::MAX;
         }
 
-        let samples: Vec<_> = self.history.iter().rev().collect();
-        let (latest_time, latest_metrics) = samples[0];
-
-        // Find most recent change in either metric
-        for (timestamp, metrics) in samples.iter().skip(1) {
-            if metrics.five_hour_pct() != latest_metrics.five_hour_pct()
-                || metrics.weekly_pct() != latest_metrics.weekly_pct()
-            {
-                return now.duration_since(**timestamp);
+        // Find the most recent step where any metric changed, other than by
+        // resetting or decaying
+        let samples: Vec<_> = self.history.iter().collect();
+        for pair in samples.windows(2).rev() {
+            let ((before_time, before), (after_time, after)) = (pair[0], pair[1]);
+            let changed = after.values.keys().chain(before.values.keys()).any(|id| {
+                self.transition(*after_time, id) == Some(TransitionKind::Increase)
+                    || after.get(id).is_some() != before.get(id).is_some()
+            });
+            if changed {
+                return now.duration_since(*before_time);
             }
         }
 
         // No change found in entire history
-        let oldest_time = samples.last().map(|(t, _)| **t).unwrap_or(*latest_time);
-        now.duration_since(oldest_time)
+        now.duration_since(*samples[0].0)
     }
 
     /// Two-tier state detection: recency gate + context analysis
@@ -240,7 +565,7 @@ impl TimeWindowedTracker {
         let recent_5h = self.calculate_five_hour_momentum(recency_window, now);
         let recent_weekly = self.calculate_weekly_momentum(recency_window, now);
 
-        if recent_5h == 0 && recent_weekly == 0 {
+        if recent_5h == Permille::ZERO && recent_weekly == Permille::ZERO {
             let idle_time = self.time_since_any_change(now);
 
             return if idle_time > Duration::from_secs(config.idle_to_cold_secs) {
@@ -254,10 +579,11 @@ impl TimeWindowedTracker {
         let context_5h = self.calculate_five_hour_momentum(context_window, now);
         let context_weekly = self.calculate_weekly_momentum(context_window, now);
 
-        let weekly_active_now = recent_weekly > 0;
-        let five_hour_active_now = recent_5h >= config.five_hour_recent_threshold;
-        let weekly_sustained = context_weekly >= config.weekly_sustained_threshold;
-        let five_hour_sustained = context_5h >= config.five_hour_sustained_threshold;
+        let threshold = Permille::from_pct;
+        let weekly_active_now = recent_weekly > Permille::ZERO;
+        let five_hour_active_now = recent_5h >= threshold(config.five_hour_recent_threshold);
+        let weekly_sustained = context_weekly >= threshold(config.weekly_sustained_threshold);
+        let five_hour_sustained = context_5h >= threshold(config.five_hour_sustained_threshold);
 
         if weekly_active_now && five_hour_active_now {
             TemperatureState::Blazing
@@ -271,66 +597,110 @@ impl TimeWindowedTracker {
     }
 }
 
-/// Adaptive polling engine with AIMD interval adjustment
+/// Adaptive polling engine
+///
+/// Tracks usage history and activity state, and delegates choosing the
+/// interval to the configured `PollingStrategy`.
 pub struct AdaptivePoller {
     current_interval: Duration,
     current_state: TemperatureState,
     tracker: TimeWindowedTracker,
+    strategy: Box<dyn PollingStrategy>,
+    jitter: Jitter,
     config: PollerConfig,
 
     state_entered_at: Instant,
+    /// How metrics moved into the latest sample
+    last_transitions: Vec<Transition>,
 }
 
 impl AdaptivePoller {
     pub fn new(config: PollerConfig) -> Self {
-        let max_history =
-            Duration::from_secs(config.context_window_secs.max(config.idle_to_cold_secs));
-
         Self {
             current_interval: Duration::from_secs(config.min_interval_secs),
             current_state: TemperatureState::Cold,
-            tracker: TimeWindowedTracker::new(max_history),
+            tracker: TimeWindowedTracker::new(Self::max_history(&config)),
+            strategy: strategy::from_config(&config),
+            jitter: Jitter::new(config.jitter),
             config,
             state_entered_at: Instant::now(),
+            last_transitions: Vec::new(),
         }
     }
 
-    fn calculate_interval_for_state(&self, state: TemperatureState) -> Duration {
-        match state {
-            TemperatureState::Cold => {
-                self.current_interval + Duration::from_secs(self.config.additive_increase_secs)
-            }
-            TemperatureState::Cool => {
-                self.current_interval + Duration::from_secs(self.config.additive_increase_secs / 2)
-            }
-            TemperatureState::Warm => Duration::from_secs(
-                (self.current_interval.as_secs() as f64 * self.config.warm_multiplier) as u64,
-            ),
-            TemperatureState::Hot => Duration::from_secs(
-                (self.current_interval.as_secs() as f64 * self.config.hot_multiplier) as u64,
-            ),
-            TemperatureState::Blazing => Duration::from_secs(
-                (self.current_interval.as_secs() as f64 * self.config.blazing_multiplier) as u64,
-            ),
-        }
+    /// Seed the jitter RNG so intervals are reproducible
+    pub fn reseed(&mut self, seed: u64) {
+        self.jitter.reseed(seed);
     }
 
-    fn get_smoothing_factor(state: TemperatureState) -> f64 {
-        match state {
-            TemperatureState::Blazing => 0.7,
-            TemperatureState::Hot => 0.5,
-            _ => 0.3,
+    fn max_history(config: &PollerConfig) -> Duration {
+        Duration::from_secs(config.context_window_secs.max(config.idle_to_cold_secs))
+    }
+
+    /// Seed the tracker from persisted samples so a restart doesn't reset to `Cold`
+    ///
+    /// `samples` are (age, metrics) pairs relative to `now`, oldest first. The
+    /// tracker keeps the ones within its history window; the strategy sees them
+    /// all. The state is re-detected from the seeded history and the interval
+    /// continues from `last_interval`.
+    pub fn restore(
+        &mut self,
+        samples: impl IntoIterator<Item = (Duration, UsageMetrics)>,
+        last_interval: Option<Duration>,
+        now: Instant,
+    ) {
+        let mut ages = Vec::new();
+        for (age, metrics) in samples {
+            if age <= self.tracker.max_history_duration
+                && let Some(at) = now.checked_sub(age)
+            {
+                self.tracker.record_sample(metrics, at);
+            }
+            ages.push(age);
         }
+        self.strategy.restore(&ages, now);
+
+        self.current_state = self.tracker.detect_state(now, &self.config);
+        self.state_entered_at = now;
+        if let Some(interval) = last_interval {
+            self.current_interval = interval.clamp(
+                Duration::from_secs(self.config.min_interval_secs),
+                Duration::from_secs(self.config.max_interval_secs),
+            );
+        }
+
+        info!(
+            state = ?self.current_state,
+            samples = self.tracker.history.len(),
+            interval_secs = self.current_interval.as_secs(),
+            strategy = self.strategy.kind().as_str(),
+            "Restored poller from history"
+        );
     }
 
-    fn apply_smoothing(current: Duration, target: Duration, factor: f64) -> Duration {
-        Duration::from_secs(
-            (current.as_secs() as f64 * (1.0 - factor) + target.as_secs() as f64 * factor) as u64,
-        )
+    /// Apply new thresholds while keeping the tracked history and current state
+    pub fn update_config(&mut self, config: PollerConfig) {
+        if config.strategy != self.strategy.kind() {
+            info!(
+                old = self.strategy.kind().as_str(),
+                new = config.strategy.as_str(),
+                "Polling strategy changed"
+            );
+            self.strategy = strategy::from_config(&config);
+        }
+        self.tracker
+            .set_max_history_duration(Self::max_history(&config));
+        self.current_interval = self.current_interval.clamp(
+            Duration::from_secs(config.min_interval_secs),
+            Duration::from_secs(config.max_interval_secs),
+        );
+        self.jitter.set_kind(config.jitter);
+        self.config = config;
     }
 
+    /// Record a sample and choose the wait until the next fetch
     pub fn next_interval(&mut self, metrics: UsageMetrics, now: Instant) -> Duration {
-        self.tracker.record_sample(metrics, now);
+        self.last_transitions = self.tracker.record_sample(metrics.clone(), now);
         let new_state = self.tracker.detect_state(now, &self.config);
 
         if new_state != self.current_state {
@@ -345,25 +715,81 @@ impl AdaptivePoller {
             self.state_entered_at = now;
         }
 
-        let new_interval = self.calculate_interval_for_state(self.current_state).clamp(
-            Duration::from_secs(self.config.min_interval_secs),
-            Duration::from_secs(self.config.max_interval_secs),
-        );
-        self.current_interval = Self::apply_smoothing(
-            self.current_interval,
-            new_interval,
-            Self::get_smoothing_factor(self.current_state),
-        );
+        self.current_interval = self.strategy.next_interval(&StrategyInput {
+            config: &self.config,
+            state: self.current_state,
+            current_interval: self.current_interval,
+            idle_time: self.tracker.time_since_any_change(now),
+            now,
+        });
+
+        // Jitter only shortens the interval and doesn't feed back into the strategy
+        let interval = if self.strategy.accepts_jitter() {
+            self.jitter.apply(
+                self.current_interval,
+                Duration::from_secs(self.config.min_interval_secs),
+                self.current_interval,
+            )
+        } else {
+            self.current_interval
+        };
 
         debug!(
             state = ?self.current_state,
+            strategy = self.strategy.kind().as_str(),
             interval_secs = self.current_interval.as_secs(),
-            five_hour_pct = metrics.five_hour_pct(),
-            weekly_pct = metrics.weekly_pct(),
+            jittered_secs = interval.as_secs(),
+            five_hour_pct = metrics.five_hour().as_pct(),
+            weekly_pct = metrics.weekly().as_pct(),
             "Calculated next interval"
         );
 
-        self.current_interval
+        interval
+    }
+
+    /// Wait before retrying a failed fetch, lengthened if the strategy counts fetches
+    pub fn retry_delay(&mut self, delay: Duration, now: Instant) -> Duration {
+        self.strategy.retry_delay(
+            delay,
+            &StrategyInput {
+                config: &self.config,
+                state: self.current_state,
+                current_interval: self.current_interval,
+                idle_time: self.tracker.time_since_any_change(now),
+                now,
+            },
+        )
+    }
+
+    /// Shorten `interval` so the next fetch lands shortly after the earliest upcoming reset
+    ///
+    /// `metrics` should be the latest sample; resets at or before `now` are ignored.
+    /// Strategies with a predictable request count opt out of this.
+    pub fn clamp_to_reset(
+        &self,
+        interval: Duration,
+        metrics: &UsageMetrics,
+        now: DateTime<Utc>,
+    ) -> Duration {
+        if !self.strategy.follows_resets() {
+            return interval;
+        }
+        let Some(until_reset) = metrics
+            .next_reset(now)
+            .and_then(|resets_at| (resets_at - now).to_std().ok())
+        else {
+            return interval;
+        };
+
+        let after_reset = until_reset + Duration::from_secs(self.config.reset_grace_secs);
+        if after_reset < interval {
+            debug!(
+                interval_secs = interval.as_secs(),
+                clamped_secs = after_reset.as_secs(),
+                "Clamping interval to the next reset"
+            );
+        }
+        interval.min(after_reset)
     }
 
     /// Get current temperature state
@@ -371,6 +797,36 @@ impl AdaptivePoller {
         self.current_state
     }
 
+    /// Increases over the context window, used to estimate the current pace
+    pub fn momentum(&self, now: Instant) -> Momentum {
+        let window = Duration::from_secs(self.config.context_window_secs);
+        let metrics = self
+            .tracker
+            .metric_ids(window, now)
+            .into_iter()
+            .map(|id| {
+                let momentum = self.tracker.calculate_metric_momentum(id, window, now);
+                (id.to_string(), momentum)
+            })
+            .collect();
+        Momentum {
+            metrics,
+            span: self.tracker.covered_span(window, now),
+        }
+    }
+
+    /// How far back `restore` can use samples, for the tracker or the strategy
+    pub fn history_window(&self) -> Duration {
+        self.tracker
+            .max_history_duration
+            .max(self.strategy.history_window())
+    }
+
+    /// How each metric moved into the sample passed to the last `next_interval`
+    pub fn last_transitions(&self) -> &[Transition] {
+        &self.last_transitions
+    }
+
     /// Get current interval
     pub fn current_interval(&self) -> Duration {
         self.current_interval
@@ -383,6 +839,10 @@ mod tests {
     use assert2::{assert, let_assert};
     use rstest::rstest;
 
+    fn pct(whole: u8) -> Permille {
+        Permille::from_whole(whole)
+    }
+
     #[test]
     fn test_initial_state_is_cold() {
         let config = PollerConfig::default();
@@ -403,46 +863,6 @@ mod tests {
         assert!(poller.current_state() == TemperatureState::Cold);
     }
 
-    #[rstest]
-    #[case(TemperatureState::Cold, 0.3)]
-    #[case(TemperatureState::Cool, 0.3)]
-    #[case(TemperatureState::Warm, 0.3)]
-    #[case(TemperatureState::Hot, 0.5)]
-    #[case(TemperatureState::Blazing, 0.7)]
-    fn test_smoothing_factors(#[case] state: TemperatureState, #[case] expected: f64) {
-        let factor = AdaptivePoller::get_smoothing_factor(state);
-        assert!(factor == expected);
-    }
-
-    #[rstest]
-    #[case(
-        Duration::from_secs(100),
-        Duration::from_secs(200),
-        0.5,
-        Duration::from_secs(150)
-    )]
-    #[case(
-        Duration::from_secs(100),
-        Duration::from_secs(300),
-        0.3,
-        Duration::from_secs(160)
-    )]
-    #[case(
-        Duration::from_secs(500),
-        Duration::from_secs(100),
-        0.7,
-        Duration::from_secs(220)
-    )]
-    fn test_smoothing_application(
-        #[case] current: Duration,
-        #[case] target: Duration,
-        #[case] factor: f64,
-        #[case] expected: Duration,
-    ) {
-        let result = AdaptivePoller::apply_smoothing(current, target, factor);
-        assert!(result == expected);
-    }
-
     #[test]
     fn test_momentum_with_increasing_five_hour() {
         let mut tracker = TimeWindowedTracker::new(Duration::from_secs(3600));
@@ -454,7 +874,7 @@ mod tests {
 
         let momentum = tracker
             .calculate_five_hour_momentum(Duration::from_secs(180), now + Duration::from_secs(120));
-        assert!(momentum == 3); // 10 → 11 → 13 = +3 total
+        assert!(momentum == pct(3)); // 10 → 11 → 13 = +3 total
     }
 
     #[test]
@@ -468,7 +888,7 @@ mod tests {
 
         let weekly_momentum = tracker
             .calculate_weekly_momentum(Duration::from_secs(180), now + Duration::from_secs(120));
-        assert!(weekly_momentum == 1); // 5 → 5 → 6 = +1 total
+        assert!(weekly_momentum == pct(1)); // 5 → 5 → 6 = +1 total
     }
 
     #[test]
@@ -481,7 +901,185 @@ mod tests {
 
         let momentum = tracker
             .calculate_five_hour_momentum(Duration::from_secs(120), now + Duration::from_secs(60));
-        assert!(momentum == 0);
+        assert!(momentum == pct(0));
+    }
+
+    #[test]
+    fn test_poller_momentum_reports_covered_span() {
+        let mut poller = AdaptivePoller::new(PollerConfig::default());
+        let now = Instant::now();
+        assert!(poller.momentum(now).span == Duration::ZERO);
+
+        poller.next_interval(UsageMetrics::new(10, 5), now);
+        poller.next_interval(UsageMetrics::new(14, 6), now + Duration::from_secs(900));
+
+        let momentum = poller.momentum(now + Duration::from_secs(1200));
+        assert!(momentum.get("five_hour") == pct(4));
+        assert!(momentum.get("seven_day") == pct(1));
+        assert!(momentum.span == Duration::from_secs(1200));
+    }
+
+    #[test]
+    fn test_reset_is_not_activity() {
+        let config = PollerConfig::default();
+        let mut tracker = TimeWindowedTracker::new(Duration::from_secs(3600));
+        let now = Instant::now();
+        let window = |resets_at: &str| {
+            let mut metrics = UsageMetrics::new(80, 40);
+            metrics.set_resets_at("five_hour", resets_at.parse().ok());
+            metrics
+        };
+
+        tracker.record_sample(window("2025-11-22T18:00:00Z"), now);
+        tracker.record_sample(
+            window("2025-11-22T18:00:00Z"),
+            now + Duration::from_secs(1500),
+        );
+        // The 5-hour window resets, then usage picks up again
+        let mut reset = UsageMetrics::new(0, 40);
+        reset.set_resets_at("five_hour", "2025-11-22T23:00:00Z".parse().ok());
+        tracker.record_sample(reset, now + Duration::from_secs(1900));
+
+        let later = now + Duration::from_secs(2000);
+        assert!(
+            tracker.transition(now + Duration::from_secs(1900), "five_hour")
+                == Some(TransitionKind::Reset)
+        );
+        assert!(tracker.time_since_any_change(later) == Duration::from_secs(2000));
+        assert!(tracker.detect_state(later, &config) == TemperatureState::Cold);
+    }
+
+    #[test]
+    fn test_momentum_skips_reset_steps() {
+        let mut tracker = TimeWindowedTracker::new(Duration::from_secs(3600));
+        let now = Instant::now();
+
+        // Without reset times any drop counts as a reset; only the climb after it is usage
+        tracker.record_sample(UsageMetrics::new(90, 5), now);
+        tracker.record_sample(UsageMetrics::new(2, 5), now + Duration::from_secs(60));
+        tracker.record_sample(UsageMetrics::new(6, 5), now + Duration::from_secs(120));
+
+        let momentum = tracker
+            .calculate_five_hour_momentum(Duration::from_secs(180), now + Duration::from_secs(120));
+        assert!(momentum == pct(4));
+    }
+
+    #[test]
+    fn test_record_sample_classifies_transitions() {
+        let mut tracker = TimeWindowedTracker::new(Duration::from_secs(3600));
+        let now = Instant::now();
+        let sample = |five_hour, weekly, resets_at: &str| {
+            let mut metrics = UsageMetrics::new(five_hour, weekly);
+            metrics.set_resets_at("five_hour", resets_at.parse().ok());
+            metrics.set_resets_at("seven_day", "2025-11-29T00:00:00Z".parse().ok());
+            metrics
+        };
+        let kinds = |transitions: Vec<Transition>| {
+            transitions
+                .into_iter()
+                .map(|t| (t.metric, t.kind, t.from.whole_pct(), t.to.whole_pct()))
+                .collect::<Vec<_>>()
+        };
+
+        let first = tracker.record_sample(sample(60, 30, "2025-11-22T18:00:00Z"), now);
+        assert!(first.is_empty());
+
+        // Old usage ages out of the rolling window while the weekly total climbs
+        let decay = tracker.record_sample(
+            sample(52, 31, "2025-11-22T18:00:00Z"),
+            now + Duration::from_secs(600),
+        );
+        assert!(
+            kinds(decay)
+                == [
+                    ("five_hour".to_string(), TransitionKind::Decay, 60, 52),
+                    ("seven_day".to_string(), TransitionKind::Increase, 30, 31),
+                ]
+        );
+
+        // A new reset time marks a hard reset, however small the drop
+        let reset = tracker.record_sample(
+            sample(50, 31, "2025-11-22T23:00:00Z"),
+            now + Duration::from_secs(1200),
+        );
+        assert!(kinds(reset) == [("five_hour".to_string(), TransitionKind::Reset, 52, 50)]);
+
+        // Decay is neither momentum nor activity
+        let later = now + Duration::from_secs(1200);
+        let momentum = tracker.calculate_five_hour_momentum(Duration::from_secs(1800), later);
+        assert!(momentum == pct(0));
+        assert!(tracker.time_since_any_change(later) == Duration::from_secs(1200));
+    }
+
+    #[rstest]
+    // Without reset times, dropping to half or less is a reset
+    #[case(90, 45, TransitionKind::Reset)]
+    #[case(90, 0, TransitionKind::Reset)]
+    #[case(90, 46, TransitionKind::Decay)]
+    #[case(10, 11, TransitionKind::Increase)]
+    fn test_transitions_without_reset_times(
+        #[case] before: u8,
+        #[case] after: u8,
+        #[case] expected: TransitionKind,
+    ) {
+        let mut tracker = TimeWindowedTracker::new(Duration::from_secs(3600));
+        let now = Instant::now();
+        tracker.record_sample(UsageMetrics::new(before, 5), now);
+
+        let transitions =
+            tracker.record_sample(UsageMetrics::new(after, 5), now + Duration::from_secs(60));
+        let_assert!([transition] = transitions.as_slice());
+        assert!(transition.kind == expected);
+    }
+
+    #[rstest]
+    // Reset in 60s: fetch 30s after it instead of waiting 10 minutes
+    #[case(Some(60), Duration::from_secs(600), Duration::from_secs(90))]
+    // Reset further away than the interval: unchanged
+    #[case(Some(3600), Duration::from_secs(600), Duration::from_secs(600))]
+    // Reset already passed: unchanged
+    #[case(Some(-60), Duration::from_secs(600), Duration::from_secs(600))]
+    #[case(None, Duration::from_secs(600), Duration::from_secs(600))]
+    fn test_clamp_to_reset(
+        #[case] resets_in_secs: Option<i64>,
+        #[case] interval: Duration,
+        #[case] expected: Duration,
+    ) {
+        let poller = AdaptivePoller::new(PollerConfig::default());
+        let now: DateTime<Utc> = "2025-11-22T12:00:00Z".parse().unwrap();
+        let mut metrics = UsageMetrics::new(50, 20);
+        metrics.set_resets_at(
+            "five_hour",
+            resets_in_secs.map(|secs| now + chrono::Duration::seconds(secs)),
+        );
+        metrics.set_resets_at("seven_day", Some(now + chrono::Duration::days(3)));
+
+        assert!(poller.clamp_to_reset(interval, &metrics, now) == expected);
+    }
+
+    #[test]
+    fn test_auxiliary_metrics_have_own_momentum() {
+        let mut poller = AdaptivePoller::new(PollerConfig::default());
+        let now = Instant::now();
+
+        let with_opus = |five_hour, opus| {
+            let mut metrics = UsageMetrics::new(five_hour, 5);
+            metrics.insert("seven_day_opus", MetricRole::Auxiliary, pct(opus));
+            metrics
+        };
+        poller.next_interval(with_opus(10, 20), now);
+        poller.next_interval(with_opus(10, 23), now + Duration::from_secs(300));
+        // A bucket that only appears later is tracked from its first sample
+        let mut latest = with_opus(11, 40);
+        latest.insert("new_bucket", MetricRole::Auxiliary, pct(7));
+        poller.next_interval(latest, now + Duration::from_secs(600));
+
+        let momentum = poller.momentum(now + Duration::from_secs(600));
+        assert!(momentum.get("five_hour") == pct(1));
+        assert!(momentum.get("seven_day") == pct(0));
+        // Auxiliary metrics aren't capped like the short window
+        assert!(momentum.get("seven_day_opus") == pct(20));
+        assert!(momentum.metrics.get("new_bucket") == Some(&Permille::ZERO));
     }
 
     #[rstest]
@@ -489,80 +1087,194 @@ mod tests {
     #[case(0, 0)]
     #[case(100, 100)]
     #[case(25, 50)]
-    fn test_valid_usage_metrics(#[case] five_hour: u8, #[case] weekly: u8) {
-        let_assert!(Ok(metrics) = UsageMetrics::try_new(five_hour, weekly));
-        assert!(metrics.five_hour_pct() == five_hour);
-        assert!(metrics.weekly_pct() == weekly);
+    fn test_usage_metrics_new(#[case] five_hour: u8, #[case] weekly: u8) {
+        let metrics = UsageMetrics::new(five_hour, weekly);
+        assert!(metrics.five_hour() == pct(five_hour));
+        assert!(metrics.weekly() == pct(weekly));
+        assert!(!metrics.is_over_limit());
     }
 
     #[rstest]
-    #[case(101, 50, UsageMetricsError::FiveHourOutOfRange(101))]
-    #[case(50, 101, UsageMetricsError::WeeklyOutOfRange(101))]
-    #[case(255, 50, UsageMetricsError::FiveHourOutOfRange(255))]
-    #[case(50, 200, UsageMetricsError::WeeklyOutOfRange(200))]
-    fn test_invalid_usage_metrics(
-        #[case] five_hour: u8,
-        #[case] weekly: u8,
-        #[case] expected_error: UsageMetricsError,
-    ) {
-        let_assert!(Err(error) = UsageMetrics::try_new(five_hour, weekly));
-        assert!(error == expected_error);
+    #[case(101, 50)]
+    #[case(50, 101)]
+    #[case(255, 50)]
+    #[case(50, 200)]
+    fn test_over_limit_usage_is_kept(#[case] five_hour: u8, #[case] weekly: u8) {
+        let metrics = UsageMetrics::new(five_hour, weekly);
+        assert!(metrics.five_hour() == pct(five_hour));
+        assert!(metrics.weekly() == pct(weekly));
+        assert!(metrics.is_over_limit());
+    }
+
+    #[test]
+    fn test_over_limit_usage_keeps_polling() {
+        let config = PollerConfig::default();
+        let mut poller = AdaptivePoller::new(config.clone());
+        let now = Instant::now();
+
+        poller.next_interval(UsageMetrics::new(98, 60), now);
+        poller.next_interval(UsageMetrics::new(100, 60), now + Duration::from_secs(180));
+        let interval =
+            poller.next_interval(UsageMetrics::new(104, 61), now + Duration::from_secs(360));
+
+        // Climbing past 100% is still activity
+        let momentum = poller.momentum(now + Duration::from_secs(360));
+        assert!(momentum.get("five_hour") == pct(6));
+        assert!(poller.current_state() != TemperatureState::Cold);
+        assert!(interval < Duration::from_secs(config.max_interval_secs));
     }
 
     #[test]
-    fn test_usage_metrics_new_succeeds() {
-        let metrics = UsageMetrics::new(25, 50);
-        assert!(metrics.five_hour_pct() == 25);
-        assert!(metrics.weekly_pct() == 50);
+    fn test_update_config_keeps_history_and_state() {
+        let mut poller = AdaptivePoller::new(PollerConfig::default());
+        let now = Instant::now();
+
+        poller.next_interval(UsageMetrics::new(10, 5), now);
+        poller.next_interval(UsageMetrics::new(14, 6), now + Duration::from_secs(60));
+        let state_before = poller.current_state();
+        assert!(state_before != TemperatureState::Cold);
+
+        poller.update_config(PollerConfig {
+            min_interval_secs: 600,
+            max_interval_secs: 1200,
+            ..Default::default()
+        });
+
+        // Interval is clamped into the new bounds, history and state survive
+        assert!(poller.current_interval() == Duration::from_secs(600));
+        assert!(poller.current_state() == state_before);
+        assert!(poller.tracker.history.len() == 2);
     }
 
     #[test]
-    #[should_panic(expected = "UsageMetrics values must be in range 0-100")]
-    fn test_usage_metrics_new_panics_on_five_hour_overflow() {
-        UsageMetrics::new(101, 50);
+    fn test_restore_detects_state_from_history() {
+        let mut poller = AdaptivePoller::new(PollerConfig::default());
+        let now = Instant::now() + Duration::from_secs(7200);
+
+        poller.restore(
+            [
+                (Duration::from_secs(540), UsageMetrics::new(10, 5)),
+                (Duration::from_secs(300), UsageMetrics::new(12, 6)),
+                (Duration::from_secs(60), UsageMetrics::new(14, 7)),
+            ],
+            Some(Duration::from_secs(400)),
+            now,
+        );
+
+        assert!(poller.current_state() == TemperatureState::Blazing);
+        assert!(poller.current_interval() == Duration::from_secs(400));
     }
 
     #[test]
-    #[should_panic(expected = "UsageMetrics values must be in range 0-100")]
-    fn test_usage_metrics_new_panics_on_weekly_overflow() {
-        UsageMetrics::new(50, 101);
+    fn test_restore_with_stale_history_stays_cold() {
+        let mut poller = AdaptivePoller::new(PollerConfig::default());
+        let now = Instant::now() + Duration::from_secs(86400);
+
+        poller.restore(
+            [
+                (Duration::from_secs(80000), UsageMetrics::new(10, 5)),
+                (Duration::from_secs(70000), UsageMetrics::new(50, 20)),
+            ],
+            Some(Duration::from_secs(10)),
+            now,
+        );
+
+        assert!(poller.current_state() == TemperatureState::Cold);
+        // Interval is clamped into the configured bounds
+        assert!(poller.current_interval() == Duration::from_secs(180));
+    }
+
+    #[rstest]
+    #[case(TemperatureState::Cold)]
+    #[case(TemperatureState::Cool)]
+    #[case(TemperatureState::Warm)]
+    #[case(TemperatureState::Hot)]
+    #[case(TemperatureState::Blazing)]
+    fn test_temperature_state_round_trip(#[case] state: TemperatureState) {
+        let_assert!(Ok(parsed) = state.as_str().parse::<TemperatureState>());
+        assert!(parsed == state);
     }
 
     #[test]
-    fn test_interval_clamping_to_min() {
+    fn test_fixed_strategy_ignores_activity_and_resets() {
         let config = PollerConfig {
-            min_interval_secs: 180,
-            max_interval_secs: 5400,
+            strategy: StrategyKind::Fixed,
+            fixed_interval_secs: 900,
             ..Default::default()
         };
         let mut poller = AdaptivePoller::new(config.clone());
+        let now = Instant::now();
 
-        poller.current_interval = Duration::from_secs(10); // Very low
-        let interval = poller.calculate_interval_for_state(TemperatureState::Blazing);
+        for i in 0..5u8 {
+            let interval = poller.next_interval(
+                UsageMetrics::new(10 + 5 * i, 5 + i),
+                now + Duration::from_secs(60 * u64::from(i)),
+            );
+            assert!(interval == Duration::from_secs(900));
+        }
+        // Activity is still tracked for display
+        assert!(poller.current_state() == TemperatureState::Blazing);
+
+        let wall_now: DateTime<Utc> = "2025-11-22T12:00:00Z".parse().unwrap();
+        let mut metrics = UsageMetrics::new(50, 20);
+        metrics.set_resets_at("five_hour", Some(wall_now + chrono::Duration::seconds(60)));
+        let interval = poller.clamp_to_reset(Duration::from_secs(900), &metrics, wall_now);
+        assert!(interval == Duration::from_secs(900));
+
+        // Switching back to the adaptive strategy takes effect on the next fetch
+        poller.update_config(PollerConfig::default());
+        let interval =
+            poller.next_interval(UsageMetrics::new(40, 10), now + Duration::from_secs(300));
+        assert!(interval < Duration::from_secs(900));
+    }
 
-        let clamped = interval.clamp(
-            Duration::from_secs(config.min_interval_secs),
-            Duration::from_secs(config.max_interval_secs),
-        );
-        assert!(clamped >= Duration::from_secs(config.min_interval_secs));
+    #[rstest]
+    #[case(42.26, 423)]
+    #[case(0.04, 0)]
+    #[case(99.95, 1000)]
+    #[case(-3.0, 0)]
+    fn test_permille_from_pct(#[case] value: f64, #[case] tenths: u16) {
+        assert!(Permille::from_pct(value) == Permille::from_tenths(tenths));
+    }
+
+    #[rstest]
+    #[case(423, 23, 400)]
+    #[case(23, 423, 0)]
+    fn test_permille_saturating_sub(#[case] a: u16, #[case] b: u16, #[case] expected: u16) {
+        let difference = Permille::from_tenths(a).saturating_sub(Permille::from_tenths(b));
+        assert!(difference == Permille::from_tenths(expected));
     }
 
     #[test]
-    fn test_interval_clamping_to_max() {
-        let config = PollerConfig {
-            min_interval_secs: 180,
-            max_interval_secs: 5400,
-            ..Default::default()
+    fn test_sub_percent_usage_registers_as_activity() {
+        let mut poller = AdaptivePoller::new(PollerConfig::default());
+        let now = Instant::now();
+        let sample = |five_hour: f64, weekly: f64| {
+            let mut metrics = UsageMetrics::default();
+            metrics.insert(
+                "five_hour",
+                MetricRole::ShortWindow,
+                Permille::from_pct(five_hour),
+            );
+            metrics.insert(
+                "seven_day",
+                MetricRole::LongWindow,
+                Permille::from_pct(weekly),
+            );
+            metrics
         };
-        let mut poller = AdaptivePoller::new(config.clone());
 
-        poller.current_interval = Duration::from_secs(10000); // Very high
-        let interval = poller.calculate_interval_for_state(TemperatureState::Cold);
+        // Weekly usage creeps up by 0.2% per poll without crossing a whole percent
+        for i in 0..4u32 {
+            let step = f64::from(i);
+            poller.next_interval(
+                sample(20.0 + step, 40.1 + 0.2 * step),
+                now + Duration::from_secs(180 * u64::from(i)),
+            );
+        }
 
-        let clamped = interval.clamp(
-            Duration::from_secs(config.min_interval_secs),
-            Duration::from_secs(config.max_interval_secs),
-        );
-        assert!(clamped <= Duration::from_secs(config.max_interval_secs));
+        let momentum = poller.momentum(now + Duration::from_secs(540));
+        assert!(momentum.get("seven_day") == Permille::from_tenths(6));
+        assert!(poller.current_state() == TemperatureState::Blazing);
     }
 }
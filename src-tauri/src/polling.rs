@@ -1,145 +1,156 @@
-use crate::api::fetch_usage_data;
-use crate::poller::{AdaptivePoller, PollerConfig, UsageMetrics};
-use crate::retry::{RetryConfig, RetryState};
-use crate::state::AppState;
+use crate::commands::CommandReceiver;
+use crate::config::{AppConfig, ConfigLoader, watch_config};
+use crate::engine::{Effect, EngineInput, PollingEngine, SystemClock};
+use crate::history::{HistoryConfig, HistoryStore};
+use crate::notify::platform::DesktopNotifier;
+use crate::notify::{Notification, Notifier};
+use crate::provider::UsageProvider;
 use crate::tray::update_tray_icon;
-use std::time::Instant;
+use std::future::pending;
+use std::time::Duration;
 use tauri::AppHandle;
-use tokio::time::sleep;
+use tokio::time::{Instant, sleep_until};
 use tokio_util::sync::CancellationToken;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 #[cfg(not(windows))]
 use crate::events::platform::start_power_listener;
 #[cfg(windows)]
 use crate::events::windows::start_power_listener;
 
-use crate::events::PollAction;
+/// How often the config file is checked for changes
+const CONFIG_WATCH_INTERVAL: Duration = Duration::from_secs(2);
 
-pub async fn start_polling(app: AppHandle, cancel_token: CancellationToken) {
-    // Initialize adaptive poller with config from environment
-    let poller_config = PollerConfig::from_env();
-    info!(
-        config = ?poller_config,
-        "Adaptive poller initialized"
-    );
+/// Open the history store, logging and continuing without history on failure
+fn open_history<P: UsageProvider>(provider: &P, config: &HistoryConfig) -> Option<HistoryStore> {
+    if !config.enabled {
+        info!("Usage history disabled");
+        return None;
+    }
+
+    let Some(path) = config.resolved_path() else {
+        warn!("No data directory available, usage history disabled");
+        return None;
+    };
 
-    let retry_config = RetryConfig::from_env();
-    info!(
-        config = ?retry_config,
-        "Retry config initialized"
-    );
+    match HistoryStore::open(&path, provider.id(), config.retention_days) {
+        Ok(store) => {
+            info!(path = %path.display(), "Usage history opened");
+            Some(store)
+        }
+        Err(e) => {
+            warn!(path = %path.display(), "Failed to open usage history: {}", e);
+            None
+        }
+    }
+}
 
-    let mut poller = AdaptivePoller::new(poller_config);
-    let mut retry_state = RetryState::new(retry_config);
-    let mut app_state = AppState::new();
+/// Show a notification without holding up the polling loop
+fn send_notification(notifier: Option<&DesktopNotifier>, notification: Notification) {
+    let Some(notifier) = notifier else {
+        return;
+    };
+    info!(summary = %notification.summary, "Sending notification");
+    let notifier = notifier.clone();
+    tokio::spawn(async move {
+        if let Err(e) = notifier.notify(&notification).await {
+            warn!("{}", e);
+        }
+    });
+}
+
+/// Carry out the engine's effects, returning when to wake it next
+fn apply_effects<P: UsageProvider>(
+    app: &AppHandle,
+    engine: &PollingEngine<P, SystemClock>,
+    notifier: Option<&DesktopNotifier>,
+    effects: Vec<Effect>,
+) -> Option<Instant> {
+    let mut wake_at = None;
+    for effect in effects {
+        match effect {
+            Effect::Render => {
+                if let Err(e) = update_tray_icon(
+                    app,
+                    engine.provider(),
+                    &engine.config().icon,
+                    engine.state(),
+                    engine.poller(),
+                    engine.retry_state(),
+                    engine.breaker(),
+                ) {
+                    error!("Failed to update tray icon: {}", e);
+                }
+            }
+            Effect::Notify(notification) => send_notification(notifier, notification),
+            Effect::SleepUntil(at) => wake_at = at,
+        }
+    }
+    wake_at
+}
+
+/// Drive a `PollingEngine` from real time, system events, commands and config changes
+pub async fn start_polling<P: UsageProvider>(
+    app: AppHandle,
+    provider: P,
+    config: AppConfig,
+    config_warning: Option<String>,
+    loader: ConfigLoader,
+    mut command_rx: CommandReceiver,
+    cancel_token: CancellationToken,
+) {
+    let history = open_history(&provider, &config.history);
+    let notifier = if config.notifications.enabled {
+        DesktopNotifier::connect()
+            .await
+            .inspect_err(|e| warn!("Notifications unavailable: {}", e))
+            .ok()
+    } else {
+        None
+    };
+
+    let mut engine = PollingEngine::new(provider, SystemClock, config).with_loader(loader.clone());
+    if let Some(store) = history {
+        engine = engine.with_history(store);
+    }
+    if let Some(warning) = config_warning {
+        engine = engine.with_config_warning(warning);
+    }
 
     // Start system event listener (Windows power management)
     let mut event_rx = start_power_listener();
-    let mut paused = false;
+
+    // Watch the config file so changes apply without a restart
+    let mut config_rx = watch_config(loader, CONFIG_WATCH_INTERVAL);
+
+    // Kept across select arms so that unrelated inputs don't restart or skip
+    // the current wait; every step returns the (possibly moved) deadline
+    let mut wake_at = engine.next_wake();
 
     loop {
-        // Check for cancellation signal and system events
-        tokio::select! {
-            _ = cancel_token.cancelled() => {
-                info!("Shutdown signal received, stopping polling gracefully");
-                break;
+        let sleep = async {
+            match wake_at {
+                Some(at) => sleep_until(at).await,
+                None => pending().await,
             }
-            Some(event) = event_rx.recv() => {
-                let action = event.recommended_action();
-                info!(?event, ?action, "System event received");
-
-                match action {
-                    PollAction::Pause => {
-                        info!("Pausing polling due to system event");
-                        paused = true;
-                    }
-                    PollAction::FetchImmediately => {
-                        if paused {
-                            info!("Resuming polling due to system event");
-                            paused = false;
-                        }
-                        // Trigger immediate fetch by continuing to next iteration
-                        continue;
-                    }
-                    PollAction::Continue => {
-                        // No action needed
-                    }
+        };
+
+        let effects = tokio::select! {
+            _ = cancel_token.cancelled() => break,
+            Some(event) = event_rx.recv() => engine.handle(EngineInput::System(event)),
+            Some(command) = command_rx.recv() => engine.handle(EngineInput::Command(command)),
+            Some(reload) = config_rx.recv() => engine.handle(EngineInput::ConfigReloaded(Box::new(reload))),
+            _ = sleep => {
+                // Abort an in-flight request on shutdown
+                tokio::select! {
+                    _ = cancel_token.cancelled() => break,
+                    effects = engine.wake() => effects,
                 }
             }
-            _ = async {
-                // Skip polling if paused
-                if paused {
-                    sleep(tokio::time::Duration::from_secs(60)).await;
-                    return;
-                }
+        };
 
-                let now = Instant::now();
-
-                info!("Fetching usage data...");
-
-                match fetch_usage_data().await {
-                    Ok(data) => {
-                        // Convert API response to metrics (rounding to 1% resolution)
-                        let metrics = UsageMetrics::new(
-                            data.five_hour.utilization.round() as u8,
-                            data.seven_day.utilization.round() as u8,
-                        );
-
-                        info!(
-                            five_hour_pct = metrics.five_hour_pct(),
-                            weekly_pct = metrics.weekly_pct(),
-                            "Usage data fetched"
-                        );
-
-                        // Update state with fresh data
-                        app_state.update_success(metrics, data);
-                        retry_state.record_success();
-
-                        // Calculate next interval using adaptive algorithm
-                        let next_interval = poller.next_interval(metrics, now);
-
-                        info!(
-                            state = ?poller.current_state(),
-                            next_interval_secs = next_interval.as_secs(),
-                            next_interval_mins = next_interval.as_secs() / 60,
-                            "Adaptive polling cycle complete"
-                        );
-
-                        // Update tray icon with current state
-                        if let Err(e) = update_tray_icon(&app, &app_state, &poller, &retry_state) {
-                            error!("Failed to update tray icon: {}", e);
-                        }
-
-                        // Sleep for adaptive duration
-                        sleep(next_interval).await;
-                    }
-                    Err(e) => {
-                        error!("Failed to fetch usage data: {}", e);
-
-                        // Calculate retry delay with exponential backoff
-                        let retry_delay = retry_state.record_failure(&e);
-
-                        // Update state with error (keeps last-known-good data)
-                        app_state.update_error(e.clone());
-
-                        // Update tray icon to show error state
-                        if let Err(icon_err) = update_tray_icon(&app, &app_state, &poller, &retry_state) {
-                            error!("Failed to update tray icon: {}", icon_err);
-                        }
-
-                        info!(
-                            error_category = e.category(),
-                            is_transient = e.is_transient(),
-                            retry_delay_secs = retry_delay.as_secs(),
-                            "Retrying after error"
-                        );
-
-                        // Sleep for calculated retry delay
-                        sleep(retry_delay).await;
-                    }
-                }
-            } => {}
-        }
+        wake_at = apply_effects(&app, &engine, notifier.as_ref(), effects);
     }
+
+    info!("Shutdown signal received, stopping polling gracefully");
 }
@@ -0,0 +1,123 @@
+use crate::config::AppConfig;
+use crate::error::FetchError;
+use crate::poller::{Permille, UsageMetrics};
+use crate::state::UsagePeriod;
+use std::collections::BTreeMap;
+use std::future::Future;
+
+/// Role a metric plays in adaptive polling and icon rendering
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricRole {
+    /// Short rolling window (drives the 5h-style momentum detection)
+    ShortWindow,
+    /// Long rolling window (drives the weekly-style detection and the tray icon)
+    LongWindow,
+    /// Informational metric, displayed but not used for polling decisions
+    Auxiliary,
+}
+
+/// Static description of a metric exposed by a provider
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MetricDescriptor {
+    /// Stable identifier, used as the key in `UsageSnapshot::periods`
+    pub id: &'static str,
+    /// Human-readable label for tooltips and menus
+    pub label: &'static str,
+    pub role: MetricRole,
+}
+
+/// Provider-agnostic result of a single successful fetch
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UsageSnapshot {
+    /// Usage periods keyed by `MetricDescriptor::id`
+    pub periods: BTreeMap<String, UsagePeriod>,
+    /// Status of the HTTP response the snapshot came from, if any
+    pub http_status: Option<u16>,
+}
+
+impl UsageSnapshot {
+    pub fn get(&self, id: &str) -> Option<&UsagePeriod> {
+        self.periods.get(id)
+    }
+
+    /// Find the period for the first descriptor with the given role
+    pub fn by_role<'a>(
+        &'a self,
+        descriptors: &'a [MetricDescriptor],
+        role: MetricRole,
+    ) -> Option<(&'a MetricDescriptor, &'a UsagePeriod)> {
+        descriptors
+            .iter()
+            .filter(|d| d.role == role)
+            .find_map(|d| self.get(d.id).map(|period| (d, period)))
+    }
+
+    /// Convert the snapshot into poller metrics (rounding to 0.1% resolution)
+    ///
+    /// Every period is included; ones the provider doesn't describe (new
+    /// buckets the API started returning) are tracked as auxiliary metrics.
+    pub fn usage_metrics(
+        &self,
+        descriptors: &[MetricDescriptor],
+    ) -> Result<UsageMetrics, FetchError> {
+        self.by_role(descriptors, MetricRole::ShortWindow)
+            .ok_or_else(|| FetchError::Parse("Missing short window metric".to_string()))?;
+        self.by_role(descriptors, MetricRole::LongWindow)
+            .ok_or_else(|| FetchError::Parse("Missing long window metric".to_string()))?;
+
+        let mut metrics = UsageMetrics::default();
+        for (id, period) in &self.periods {
+            let role = descriptors
+                .iter()
+                .find(|d| d.id == id)
+                .map_or(MetricRole::Auxiliary, |d| d.role);
+            metrics.insert(id, role, Permille::from_pct(period.utilization));
+            metrics.set_resets_at(id, period.resets_at);
+        }
+        Ok(metrics)
+    }
+
+    /// Periods not covered by `descriptors`, in id order
+    pub fn undescribed<'a>(
+        &'a self,
+        descriptors: &'a [MetricDescriptor],
+    ) -> impl Iterator<Item = (&'a str, &'a UsagePeriod)> {
+        self.periods
+            .iter()
+            .filter(|(id, _)| !descriptors.iter().any(|d| d.id == id.as_str()))
+            .map(|(id, period)| (id.as_str(), period))
+    }
+}
+
+/// Human-readable label for a metric id without a descriptor
+///
+/// e.g. "seven_day_oauth_apps" becomes "Seven day oauth apps".
+pub fn fallback_label(id: &str) -> String {
+    let words = id.replace('_', " ");
+    let mut chars = words.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// A rate-limited service whose usage can be polled
+///
+/// The polling loop, `AppState` and the tray only talk to this trait, so new
+/// services can be added without touching the poller.
+pub trait UsageProvider: Send + Sync + 'static {
+    /// Stable identifier for the provider (e.g. "claude")
+    fn id(&self) -> &'static str;
+
+    /// Human-readable name shown in the tray
+    fn display_name(&self) -> &str;
+
+    /// Metrics reported by this provider, in display order
+    fn metrics(&self) -> &[MetricDescriptor];
+
+    /// Apply a reloaded configuration (e.g. new credentials)
+    fn reconfigure(&mut self, _config: &AppConfig) {}
+
+    /// Fetch the current usage snapshot
+    fn fetch(&self) -> impl Future<Output = Result<UsageSnapshot, FetchError>> + Send;
+}
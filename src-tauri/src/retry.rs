@@ -1,12 +1,20 @@
 use crate::error::FetchError;
+use crate::jitter::{Jitter, JitterKind};
+use serde::Deserialize;
 use tokio::time::Duration;
 
+/// Longest server-requested delay honored unless `max_delay_secs` is longer (one day)
+const MAX_RETRY_AFTER_SECS: u64 = 24 * 60 * 60;
+
 /// Configuration for retry behavior
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct RetryConfig {
-    min_delay_secs: u64,
-    max_delay_secs: u64,
-    multiplier: f64,
+    pub min_delay_secs: u64,
+    pub max_delay_secs: u64,
+    pub multiplier: f64,
+    /// Randomness added to backoff delays so clients don't retry in lockstep
+    pub jitter: JitterKind,
 }
 
 impl Default for RetryConfig {
@@ -15,39 +23,19 @@ impl Default for RetryConfig {
             min_delay_secs: 5,   // 5 seconds
             max_delay_secs: 300, // 5 minutes
             multiplier: 2.0,     // Double each time
+            jitter: JitterKind::None,
         }
     }
 }
 
-impl RetryConfig {
-    pub fn from_env() -> Self {
-        let mut config = Self::default();
-
-        if let Ok(val) = std::env::var("RETRY_MIN_DELAY_SECS")
-            && let Ok(parsed) = val.parse()
-        {
-            config.min_delay_secs = parsed;
-        }
-        if let Ok(val) = std::env::var("RETRY_MAX_DELAY_SECS")
-            && let Ok(parsed) = val.parse()
-        {
-            config.max_delay_secs = parsed;
-        }
-        if let Ok(val) = std::env::var("RETRY_MULTIPLIER")
-            && let Ok(parsed) = val.parse()
-        {
-            config.multiplier = parsed;
-        }
-
-        config
-    }
-}
-
 /// Tracks retry state with exponential backoff
 #[derive(Debug)]
 pub struct RetryState {
     current_delay: Duration,
+    /// Delay returned by the most recent `record_failure`
+    last_delay: Duration,
     consecutive_failures: u32,
+    jitter: Jitter,
     config: RetryConfig,
 }
 
@@ -55,15 +43,41 @@ impl RetryState {
     pub fn new(config: RetryConfig) -> Self {
         Self {
             current_delay: Duration::from_secs(config.min_delay_secs),
+            last_delay: Duration::from_secs(config.min_delay_secs),
             consecutive_failures: 0,
+            jitter: Jitter::new(config.jitter),
             config,
         }
     }
 
+    /// Seed the jitter RNG so delays are reproducible
+    pub fn reseed(&mut self, seed: u64) {
+        self.jitter.reseed(seed);
+    }
+
+    /// Swap the retry configuration, keeping the failure count
+    pub fn update_config(&mut self, config: RetryConfig) {
+        self.current_delay = self.current_delay.clamp(
+            Duration::from_secs(config.min_delay_secs),
+            Duration::from_secs(config.max_delay_secs),
+        );
+        self.jitter.set_kind(config.jitter);
+        self.config = config;
+    }
+
     /// Record a successful fetch - resets backoff
     pub fn record_success(&mut self) {
         self.current_delay = Duration::from_secs(self.config.min_delay_secs);
+        self.last_delay = self.current_delay;
         self.consecutive_failures = 0;
+        self.jitter.reset();
+    }
+
+    /// Forget previous failures, returning whether a backoff was in progress
+    pub fn reset(&mut self) -> bool {
+        let was_backing_off = self.consecutive_failures > 0;
+        self.record_success();
+        was_backing_off
     }
 
     /// Record a failure and calculate next delay with exponential backoff
@@ -78,23 +92,118 @@ impl RetryState {
                 .max(self.config.min_delay_secs)
                 .min(self.config.max_delay_secs);
 
+            // Jitter the delay but keep growing from the computed one
             self.current_delay = Duration::from_secs(clamped);
-            self.current_delay
+            self.jitter.apply(
+                self.current_delay,
+                Duration::from_secs(self.config.min_delay_secs),
+                Duration::from_secs(self.config.max_delay_secs),
+            )
         } else {
             // Permanent errors: use minimum interval (don't spam, but stay responsive)
             Duration::from_secs(self.config.min_delay_secs)
         };
 
-        // Extra backoff for rate limiting
-        if matches!(error, FetchError::RateLimited { .. }) {
-            // Use max delay for rate limits to avoid further limiting
-            Duration::from_secs(self.config.max_delay_secs)
-        } else {
-            delay
+        self.last_delay = match error {
+            // Honor the server-provided delay, within sane bounds
+            FetchError::RateLimited {
+                retry_after: Some(seconds),
+                ..
+            } => {
+                let ceiling = MAX_RETRY_AFTER_SECS.max(self.config.max_delay_secs);
+                Duration::from_secs((*seconds).clamp(self.config.min_delay_secs, ceiling))
+            }
+            // Otherwise use max delay for rate limits to avoid further limiting
+            FetchError::RateLimited {
+                retry_after: None, ..
+            } => Duration::from_secs(self.config.max_delay_secs),
+            _ => delay,
+        };
+
+        self.last_delay
+    }
+
+    /// Delay chosen by the most recent failure (what the loop is actually sleeping)
+    pub fn last_delay(&self) -> Duration {
+        self.last_delay
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::NetworkErrorKind;
+    use assert2::assert;
+    use rstest::rstest;
+
+    fn rate_limited(retry_after: Option<u64>) -> FetchError {
+        FetchError::RateLimited {
+            message: "Too many requests".to_string(),
+            retry_after,
         }
     }
 
-    pub fn current_delay(&self) -> Duration {
-        self.current_delay
+    #[rstest]
+    #[case(Some(42), Duration::from_secs(42))]
+    #[case(Some(900), Duration::from_secs(900))]
+    #[case(Some(0), Duration::from_secs(5))]
+    #[case(Some(u64::MAX), Duration::from_secs(24 * 60 * 60))]
+    #[case(None, Duration::from_secs(300))]
+    fn test_rate_limit_delay(#[case] retry_after: Option<u64>, #[case] expected: Duration) {
+        let mut state = RetryState::new(RetryConfig::default());
+
+        let delay = state.record_failure(&rate_limited(retry_after));
+        assert!(delay == expected);
+        assert!(state.last_delay() == expected);
+    }
+
+    #[test]
+    fn test_transient_errors_back_off_exponentially() {
+        let mut state = RetryState::new(RetryConfig::default());
+        let error = FetchError::network(NetworkErrorKind::Connect, "Connection reset");
+
+        assert!(state.record_failure(&error) == Duration::from_secs(10));
+        assert!(state.record_failure(&error) == Duration::from_secs(20));
+
+        state.record_success();
+        assert!(state.last_delay() == Duration::from_secs(5));
+        assert!(state.record_failure(&error) == Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_seeded_jitter_is_reproducible() {
+        let config = RetryConfig {
+            jitter: JitterKind::Full,
+            ..RetryConfig::default()
+        };
+        let error = FetchError::network(NetworkErrorKind::Connect, "Connection reset");
+        let delays = |seed| {
+            let mut state = RetryState::new(config.clone());
+            state.reseed(seed);
+            (0..6)
+                .map(|_| state.record_failure(&error))
+                .collect::<Vec<_>>()
+        };
+
+        let delays_a = delays(7);
+        assert!(delays_a == delays(7));
+        assert!(delays_a != delays(8));
+        // Never longer than the un-jittered backoff: 10, 20, 40, 80, 160, 300
+        for (delay, limit) in delays_a.iter().zip([10, 20, 40, 80, 160, 300]) {
+            assert!(*delay >= Duration::from_secs(5) && *delay <= Duration::from_secs(limit));
+        }
+    }
+
+    #[test]
+    fn test_reset_reports_backoff() {
+        let mut state = RetryState::new(RetryConfig::default());
+        assert!(!state.reset());
+
+        state.record_failure(&FetchError::network(
+            NetworkErrorKind::Connect,
+            "Connection reset",
+        ));
+        assert!(state.reset());
+        assert!(!state.reset());
     }
 }
@@ -0,0 +1,392 @@
+use crate::error::FetchError;
+use crate::state::{UsageData, UsagePeriod};
+use chrono::DateTime;
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// Top-level periods the app can't work without
+const REQUIRED_PERIODS: &[&str] = &["five_hour", "seven_day"];
+
+/// Top-level periods that are used when present
+const OPTIONAL_PERIODS: &[&str] = &["seven_day_oauth_apps", "seven_day_opus", "iguana_necktie"];
+
+/// Fields of a usage period object
+const PERIOD_FIELDS: &[&str] = &["utilization", "resets_at"];
+
+/// Largest raw response body saved as a sample
+const MAX_SAMPLE_BYTES: usize = 64 * 1024;
+
+/// Differences between a usage response and the shape the app expects
+///
+/// Nested fields are reported as `period.field`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SchemaReport {
+    /// Fields the app doesn't know about
+    pub unknown: Vec<String>,
+    /// Known fields that were absent
+    pub missing: Vec<String>,
+    /// Known fields with an unexpected type
+    pub malformed: Vec<String>,
+}
+
+impl SchemaReport {
+    pub fn is_empty(&self) -> bool {
+        self.unknown.is_empty() && self.missing.is_empty() && self.malformed.is_empty()
+    }
+
+    /// Stable identifier of this shape (FNV-1a over the sorted field lists)
+    pub fn fingerprint(&self) -> u64 {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for (tag, fields) in [
+            ("unknown", &self.unknown),
+            ("missing", &self.missing),
+            ("malformed", &self.malformed),
+        ] {
+            let mut fields = fields.clone();
+            fields.sort();
+            for byte in std::iter::once(tag)
+                .chain(fields.iter().map(String::as_str))
+                .flat_map(|s| s.bytes().chain([0]))
+            {
+                hash ^= u64::from(byte);
+                hash = hash.wrapping_mul(0x0100_0000_01b3);
+            }
+        }
+        hash
+    }
+}
+
+/// Parse a usage response, tolerating fields being added, dropped or changed
+///
+/// Only a missing or malformed required period is an error. Optional periods
+/// that are missing or malformed (including a bad `resets_at`) become `None`,
+/// and unknown top-level fields are kept in `UsageData::other`. The report
+/// describes every deviation, including when parsing fails.
+pub fn parse_usage(body: &str) -> (Result<UsageData, FetchError>, SchemaReport) {
+    let mut report = SchemaReport::default();
+
+    let value: Value = match serde_json::from_str(body) {
+        Ok(value) => value,
+        Err(e) => {
+            return (
+                Err(FetchError::Parse(format!(
+                    "Failed to parse response: {}",
+                    e
+                ))),
+                report,
+            );
+        }
+    };
+    let Value::Object(mut fields) = value else {
+        report.malformed.push("<root>".to_string());
+        return (
+            Err(FetchError::Parse(
+                "Failed to parse response: expected a JSON object".to_string(),
+            )),
+            report,
+        );
+    };
+
+    let mut required = Vec::new();
+    let mut errors = Vec::new();
+    for name in REQUIRED_PERIODS {
+        match take_period(&mut fields, name, &mut report) {
+            Ok(Some(period)) => required.push(period),
+            Ok(None) => errors.push(format!("`{}` is null", name)),
+            Err(problem) => errors.push(problem.describe(name)),
+        }
+    }
+
+    let mut optional: BTreeMap<&str, UsagePeriod> = BTreeMap::new();
+    for name in OPTIONAL_PERIODS {
+        match take_period(&mut fields, name, &mut report) {
+            Ok(Some(period)) => {
+                optional.insert(name, period);
+            }
+            // Dropped rather than shown with a reset time that can't be trusted
+            Err(problem @ PeriodProblem::Timestamp(_)) => {
+                warn!("Ignoring optional period: {}", problem.describe(name));
+            }
+            Ok(None) | Err(_) => {}
+        }
+    }
+
+    report.unknown.extend(fields.keys().cloned());
+
+    if !errors.is_empty() {
+        return (
+            Err(FetchError::Parse(format!(
+                "Failed to parse response: {}",
+                errors.join(", ")
+            ))),
+            report,
+        );
+    }
+
+    let mut required = required.into_iter();
+    let data = UsageData {
+        five_hour: required.next().expect("checked above"),
+        seven_day: required.next().expect("checked above"),
+        seven_day_oauth_apps: optional.remove("seven_day_oauth_apps"),
+        seven_day_opus: optional.remove("seven_day_opus"),
+        iguana_necktie: optional.remove("iguana_necktie"),
+        other: fields.into_iter().collect(),
+    };
+    (Ok(data), report)
+}
+
+/// Why a period field couldn't be used
+enum PeriodProblem {
+    Missing,
+    Malformed,
+    /// `resets_at` isn't an RFC 3339 timestamp
+    Timestamp(String),
+}
+
+impl PeriodProblem {
+    fn describe(&self, name: &str) -> String {
+        match self {
+            Self::Missing => format!("missing `{}`", name),
+            Self::Malformed => format!("malformed `{}`", name),
+            Self::Timestamp(detail) => format!("invalid `{}.resets_at`: {}", name, detail),
+        }
+    }
+}
+
+/// Remove and parse a period field, recording deviations in the report
+///
+/// Returns `Ok(None)` when the field is null.
+fn take_period(
+    fields: &mut Map<String, Value>,
+    name: &str,
+    report: &mut SchemaReport,
+) -> Result<Option<UsagePeriod>, PeriodProblem> {
+    let Some(value) = fields.remove(name) else {
+        report.missing.push(name.to_string());
+        return Err(PeriodProblem::Missing);
+    };
+    if value.is_null() {
+        return Ok(None);
+    }
+
+    if let Value::Object(period) = &value {
+        report.unknown.extend(
+            period
+                .keys()
+                .filter(|key| !PERIOD_FIELDS.contains(&key.as_str()))
+                .map(|key| format!("{}.{}", name, key)),
+        );
+
+        if let Some(Value::String(raw)) = period.get("resets_at")
+            && let Err(e) = DateTime::parse_from_rfc3339(raw)
+        {
+            report.malformed.push(format!("{}.resets_at", name));
+            return Err(PeriodProblem::Timestamp(format!("{:?} ({})", raw, e)));
+        }
+    }
+
+    serde_json::from_value::<UsagePeriod>(value)
+        .map(Some)
+        .map_err(|_| {
+            report.malformed.push(name.to_string());
+            PeriodProblem::Malformed
+        })
+}
+
+/// Logs each new response shape once and keeps a sample of its raw body
+#[derive(Debug, Default)]
+pub struct DriftRecorder {
+    /// Where samples are written (None to only log)
+    sample_dir: Option<PathBuf>,
+    seen: HashSet<u64>,
+}
+
+impl DriftRecorder {
+    pub fn new(sample_dir: Option<PathBuf>) -> Self {
+        Self {
+            sample_dir,
+            seen: HashSet::new(),
+        }
+    }
+
+    /// Default sample directory inside the platform data directory
+    pub fn default_sample_dir() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("usage-indicator").join("schema-drift"))
+    }
+
+    /// Report `report` if this shape hasn't been seen, returning whether it was new
+    ///
+    /// A sample from an earlier run counts as seen, so restarts don't add
+    /// duplicate samples (the log line is still written once per run).
+    pub fn observe(&mut self, report: &SchemaReport, body: &str) -> bool {
+        if report.is_empty() {
+            return false;
+        }
+        let fingerprint = report.fingerprint();
+        if !self.seen.insert(fingerprint) {
+            return false;
+        }
+
+        warn!(
+            fingerprint = %format!("{:016x}", fingerprint),
+            unknown = ?report.unknown,
+            missing = ?report.missing,
+            malformed = ?report.malformed,
+            "Schema drift in usage response"
+        );
+
+        if let Some(dir) = &self.sample_dir {
+            let path = dir.join(format!("usage-{:016x}.json", fingerprint));
+            if !path.exists() {
+                match save_sample(&path, body) {
+                    Ok(()) => info!(path = %path.display(), "Saved usage response sample"),
+                    Err(e) => {
+                        warn!(path = %path.display(), "Failed to save response sample: {}", e)
+                    }
+                }
+            }
+        }
+        true
+    }
+}
+
+fn save_sample(path: &Path, body: &str) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut end = body.len().min(MAX_SAMPLE_BYTES);
+    while !body.is_char_boundary(end) {
+        end -= 1;
+    }
+    std::fs::write(path, &body[..end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::{assert, let_assert};
+    use rstest::rstest;
+    use serde_json::json;
+
+    fn period(utilization: f64) -> Value {
+        json!({"utilization": utilization, "resets_at": "2025-11-29T00:00:00Z"})
+    }
+
+    fn expected_body() -> Value {
+        json!({
+            "five_hour": period(10.0),
+            "seven_day": period(20.0),
+            "seven_day_oauth_apps": null,
+            "seven_day_opus": period(0.0),
+            "iguana_necktie": null
+        })
+    }
+
+    #[test]
+    fn test_expected_shape_has_no_drift() {
+        let (result, report) = parse_usage(&expected_body().to_string());
+        let_assert!(Ok(data) = result);
+        assert!(data.five_hour.utilization == 10.0);
+        assert!(data.seven_day_opus.is_some());
+        assert!(report.is_empty());
+    }
+
+    #[test]
+    fn test_optional_and_unknown_fields_degrade() {
+        let mut body = expected_body();
+        let fields = body.as_object_mut().unwrap();
+        fields.remove("iguana_necktie");
+        fields.insert("seven_day_opus".to_string(), json!("n/a"));
+        fields.insert("seven_day_sonnet".to_string(), period(5.0));
+        fields["five_hour"]["window_secs"] = json!(18000);
+
+        let (result, report) = parse_usage(&body.to_string());
+        let_assert!(Ok(data) = result);
+        assert!(data.seven_day_opus.is_none());
+        assert!(data.other.contains_key("seven_day_sonnet"));
+        assert!(report.missing == ["iguana_necktie"]);
+        assert!(report.malformed == ["seven_day_opus"]);
+        assert!(report.unknown == ["five_hour.window_secs", "seven_day_sonnet"]);
+    }
+
+    #[test]
+    fn test_missing_required_field_is_a_parse_error() {
+        let mut body = expected_body();
+        body.as_object_mut().unwrap().remove("seven_day");
+        body["five_hour"] = json!({"utilization": "high"});
+
+        let (result, report) = parse_usage(&body.to_string());
+        let_assert!(Err(FetchError::Parse(message)) = result);
+        assert!(message.contains("malformed `five_hour`"));
+        assert!(message.contains("missing `seven_day`"));
+        assert!(report.missing == ["seven_day"]);
+        assert!(report.malformed == ["five_hour"]);
+    }
+
+    #[rstest]
+    #[case("five_hour")]
+    #[case("seven_day")]
+    fn test_malformed_timestamp_is_a_parse_error(#[case] name: &str) {
+        let mut body = expected_body();
+        body[name]["resets_at"] = json!("next Thursday");
+
+        let (result, report) = parse_usage(&body.to_string());
+        let_assert!(Err(FetchError::Parse(message)) = result);
+        assert!(message.contains(&format!("invalid `{}.resets_at`: \"next Thursday\"", name)));
+        assert!(report.malformed == [format!("{}.resets_at", name)]);
+    }
+
+    #[test]
+    fn test_malformed_optional_timestamp_drops_the_period() {
+        let mut body = expected_body();
+        body["seven_day_opus"]["resets_at"] = json!("next Thursday");
+
+        let (result, report) = parse_usage(&body.to_string());
+        let_assert!(Ok(data) = result);
+        assert!(data.seven_day_opus.is_none());
+        assert!(data.five_hour.utilization == 10.0);
+        assert!(report.malformed == ["seven_day_opus.resets_at"]);
+    }
+
+    #[test]
+    fn test_fingerprint_ignores_field_order() {
+        let a = SchemaReport {
+            unknown: vec!["a".to_string(), "b".to_string()],
+            ..Default::default()
+        };
+        let b = SchemaReport {
+            unknown: vec!["b".to_string(), "a".to_string()],
+            ..Default::default()
+        };
+        let c = SchemaReport {
+            missing: vec!["a".to_string(), "b".to_string()],
+            ..Default::default()
+        };
+        assert!(a.fingerprint() == b.fingerprint());
+        assert!(a.fingerprint() != c.fingerprint());
+    }
+
+    #[test]
+    fn test_recorder_saves_each_new_shape_once() {
+        let dir = std::env::temp_dir().join(format!("usage-drift-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let mut recorder = DriftRecorder::new(Some(dir.clone()));
+
+        let report = SchemaReport {
+            unknown: vec!["seven_day_sonnet".to_string()],
+            ..Default::default()
+        };
+        assert!(!recorder.observe(&SchemaReport::default(), "{}"));
+        assert!(recorder.observe(&report, "{\"sample\":1}"));
+        assert!(!recorder.observe(&report, "{\"sample\":2}"));
+
+        let samples: Vec<_> = std::fs::read_dir(&dir).unwrap().collect();
+        assert!(samples.len() == 1);
+        let_assert!(Ok(entry) = &samples[0]);
+        assert!(std::fs::read_to_string(entry.path()).unwrap() == "{\"sample\":1}");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
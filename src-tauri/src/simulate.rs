@@ -0,0 +1,422 @@
+use crate::config::{AppConfig, ConfigLoader};
+use crate::forecast::{DurationStyle, format_duration};
+use crate::poller::{AdaptivePoller, Permille, PollerConfig, TemperatureState, UsageMetrics};
+use crate::provider::MetricRole;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::fmt::Write as _;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+const STATES: [TemperatureState; 5] = [
+    TemperatureState::Cold,
+    TemperatureState::Cool,
+    TemperatureState::Warm,
+    TemperatureState::Hot,
+    TemperatureState::Blazing,
+];
+
+/// Arguments of the `simulate` subcommand
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulateArgs {
+    /// CSV or JSON trace of recorded usage
+    pub trace: PathBuf,
+    /// Config file to compare against the loaded configuration
+    pub compare: Option<PathBuf>,
+}
+
+/// One recorded observation of usage
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct TraceSample {
+    pub timestamp: DateTime<Utc>,
+    pub five_hour: f64,
+    pub weekly: f64,
+}
+
+impl TraceSample {
+    fn metrics(&self) -> UsageMetrics {
+        let mut metrics = UsageMetrics::default();
+        metrics.insert(
+            "five_hour",
+            MetricRole::ShortWindow,
+            Permille::from_pct(self.five_hour),
+        );
+        metrics.insert(
+            "seven_day",
+            MetricRole::LongWindow,
+            Permille::from_pct(self.weekly),
+        );
+        metrics
+    }
+}
+
+/// Parse a trace as a JSON array of samples or as `timestamp,five_hour,weekly` CSV
+///
+/// CSV may start with a header row; blank lines and `#` comments are skipped.
+/// Samples are sorted by timestamp.
+pub fn parse_trace(source: &str) -> Result<Vec<TraceSample>, String> {
+    let mut samples = if source.trim_start().starts_with('[') {
+        serde_json::from_str::<Vec<TraceSample>>(source)
+            .map_err(|e| format!("invalid JSON trace: {}", e))?
+    } else {
+        parse_csv(source)?
+    };
+
+    if samples.is_empty() {
+        return Err("trace has no samples".to_string());
+    }
+    samples.sort_by_key(|sample| sample.timestamp);
+    Ok(samples)
+}
+
+fn parse_csv(source: &str) -> Result<Vec<TraceSample>, String> {
+    let mut samples = Vec::new();
+    for (index, line) in source.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with("timestamp") {
+            continue;
+        }
+
+        let line_no = index + 1;
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let [timestamp, five_hour, weekly] = fields[..] else {
+            return Err(format!(
+                "line {}: expected timestamp,five_hour,weekly",
+                line_no
+            ));
+        };
+        let number = |field: &str| {
+            field
+                .parse::<f64>()
+                .map_err(|_| format!("line {}: invalid number '{}'", line_no, field))
+        };
+        samples.push(TraceSample {
+            timestamp: timestamp
+                .parse()
+                .map_err(|_| format!("line {}: invalid timestamp '{}'", line_no, timestamp))?,
+            five_hour: number(five_hour)?,
+            weekly: number(weekly)?,
+        });
+    }
+    Ok(samples)
+}
+
+/// Outcome of replaying a trace against one poller configuration
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulationReport {
+    /// Fetches made over the trace
+    pub requests: usize,
+    /// Time covered by the trace
+    pub span: Duration,
+    /// Delay between each usage increase and the first fetch to see it, sorted
+    pub latencies: Vec<Duration>,
+    /// Increases that happened after the last fetch of the trace
+    pub missed: usize,
+    /// Time spent in each temperature state, in `STATES` order
+    pub time_in_state: Vec<(TemperatureState, Duration)>,
+}
+
+impl SimulationReport {
+    /// Latency at percentile `p` (0-100), if any increase was detected
+    pub fn latency_percentile(&self, p: usize) -> Option<Duration> {
+        let last = self.latencies.len().checked_sub(1)?;
+        Some(self.latencies[(last * p.min(100)).div_ceil(100)])
+    }
+
+    pub fn mean_latency(&self) -> Option<Duration> {
+        let count = u32::try_from(self.latencies.len())
+            .ok()
+            .filter(|&n| n > 0)?;
+        Some(self.latencies.iter().sum::<Duration>() / count)
+    }
+}
+
+/// Replay `trace` through an `AdaptivePoller` on a simulated clock
+///
+/// Each fetch sees the latest sample at or before the fetch time. The first
+/// fetch happens at the first sample and the run ends after the last one.
+pub fn simulate(trace: &[TraceSample], config: &PollerConfig) -> Result<SimulationReport, String> {
+    let (Some(first), Some(last)) = (trace.first(), trace.last()) else {
+        return Err("trace has no samples".to_string());
+    };
+    let start = first.timestamp;
+    let end = last.timestamp;
+    let offset = |at: DateTime<Utc>| (at - start).to_std().unwrap_or_default();
+
+    // The poller only compares instants, so any base works
+    let base = Instant::now();
+    let mut poller = AdaptivePoller::new(config.clone());
+    // A fixed seed keeps runs with jitter reproducible
+    poller.reseed(0);
+    let mut fetches = Vec::new();
+    let mut time_in_state: Vec<_> = STATES.iter().map(|&s| (s, Duration::ZERO)).collect();
+
+    let mut at = start;
+    let mut next_sample = 0;
+    while at <= end {
+        while next_sample + 1 < trace.len() && trace[next_sample + 1].timestamp <= at {
+            next_sample += 1;
+        }
+        let metrics = trace[next_sample].metrics();
+        let interval = poller
+            .next_interval(metrics, base + offset(at))
+            .max(Duration::from_secs(1));
+        fetches.push(at);
+
+        let state = poller.current_state();
+        let until = (at + interval).min(end);
+        if let Some((_, spent)) = time_in_state.iter_mut().find(|(s, _)| *s == state) {
+            *spent += offset(until).saturating_sub(offset(at));
+        }
+        at += interval;
+    }
+
+    let mut latencies = Vec::new();
+    let mut missed = 0;
+    for pair in trace.windows(2) {
+        let (before, after) = (&pair[0], &pair[1]);
+        let increased =
+            |before: f64, after: f64| Permille::from_pct(after) > Permille::from_pct(before);
+        if !increased(before.five_hour, after.five_hour) && !increased(before.weekly, after.weekly)
+        {
+            continue;
+        }
+        match fetches.iter().find(|&&fetch| fetch >= after.timestamp) {
+            Some(&fetch) => latencies.push((fetch - after.timestamp).to_std().unwrap_or_default()),
+            None => missed += 1,
+        }
+    }
+    latencies.sort();
+
+    Ok(SimulationReport {
+        requests: fetches.len(),
+        span: offset(end),
+        latencies,
+        missed,
+        time_in_state,
+    })
+}
+
+/// Render reports side by side, one column per (label, report)
+pub fn format_reports(columns: &[(&str, &SimulationReport)]) -> String {
+    let precise = |d: Duration| format_duration(d, DurationStyle::Precise);
+    let optional = |d: Option<Duration>| d.map_or_else(|| "-".to_string(), precise);
+    let mut rows: Vec<(String, Vec<String>)> = vec![
+        (
+            "Trace span".to_string(),
+            columns.iter().map(|(_, r)| precise(r.span)).collect(),
+        ),
+        (
+            "Requests".to_string(),
+            columns
+                .iter()
+                .map(|(_, r)| r.requests.to_string())
+                .collect(),
+        ),
+        (
+            "Increases detected".to_string(),
+            columns
+                .iter()
+                .map(|(_, r)| r.latencies.len().to_string())
+                .collect(),
+        ),
+        (
+            "Increases missed".to_string(),
+            columns.iter().map(|(_, r)| r.missed.to_string()).collect(),
+        ),
+        (
+            "Latency mean".to_string(),
+            columns
+                .iter()
+                .map(|(_, r)| optional(r.mean_latency()))
+                .collect(),
+        ),
+    ];
+    for (name, p) in [
+        ("Latency p50", 50),
+        ("Latency p90", 90),
+        ("Latency max", 100),
+    ] {
+        rows.push((
+            name.to_string(),
+            columns
+                .iter()
+                .map(|(_, r)| optional(r.latency_percentile(p)))
+                .collect(),
+        ));
+    }
+    for (index, state) in STATES.iter().enumerate() {
+        rows.push((
+            format!("Time {}", state.as_str()),
+            columns
+                .iter()
+                .map(|(_, r)| precise(r.time_in_state[index].1))
+                .collect(),
+        ));
+    }
+
+    let name_width = rows.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    let column_widths: Vec<usize> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, (label, _))| {
+            rows.iter()
+                .map(|(_, values)| values[i].len())
+                .chain([label.len()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let mut out = format!("{:name_width$}", "");
+    for ((label, _), width) in columns.iter().zip(&column_widths) {
+        let _ = write!(out, "  {:>width$}", label);
+    }
+    for (name, values) in rows {
+        let _ = write!(out, "\n{:name_width$}", name);
+        for (value, width) in values.iter().zip(&column_widths) {
+            let _ = write!(out, "  {:>width$}", value);
+        }
+    }
+    out
+}
+
+/// Run the `simulate` subcommand, returning the report to print
+///
+/// `config` is the normally loaded configuration. A `--compare` config is
+/// loaded from its file alone, so only the keys it sets differ from defaults.
+pub fn run(args: &SimulateArgs, config: &AppConfig) -> Result<String, String> {
+    let source = std::fs::read_to_string(&args.trace)
+        .map_err(|e| format!("Failed to read {}: {}", args.trace.display(), e))?;
+    let trace = parse_trace(&source).map_err(|e| format!("{}: {}", args.trace.display(), e))?;
+
+    let baseline = simulate(&trace, &config.poller)?;
+    match &args.compare {
+        None => Ok(format_reports(&[("config", &baseline)])),
+        Some(path) => {
+            let other = ConfigLoader::new(Some(path.clone()))
+                .load()
+                .map_err(|e| e.to_string())?;
+            let compared = simulate(&trace, &other.poller)?;
+            let label = path
+                .file_name()
+                .map_or_else(|| "compare".into(), |name| name.to_string_lossy());
+            Ok(format_reports(&[
+                ("config", &baseline),
+                (label.as_ref(), &compared),
+            ]))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::StrategyKind;
+    use assert2::{assert, let_assert};
+
+    fn trace(samples: &[(i64, f64, f64)]) -> Vec<TraceSample> {
+        let start: DateTime<Utc> = "2025-11-22T12:00:00Z".parse().unwrap();
+        samples
+            .iter()
+            .map(|&(secs, five_hour, weekly)| TraceSample {
+                timestamp: start + chrono::Duration::seconds(secs),
+                five_hour,
+                weekly,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_csv_and_json() {
+        let csv = "timestamp,five_hour,weekly\n\
+                   # quiet afternoon\n\
+                   2025-11-22T12:10:00Z, 12.5, 40\n\
+                   2025-11-22T12:00:00Z, 10, 40\n";
+        let_assert!(Ok(from_csv) = parse_trace(csv));
+        assert!(from_csv.len() == 2);
+        // Sorted by timestamp
+        assert!(from_csv[0].five_hour == 10.0);
+
+        let json = r#"[
+            {"timestamp": "2025-11-22T12:00:00Z", "five_hour": 10, "weekly": 40},
+            {"timestamp": "2025-11-22T12:10:00Z", "five_hour": 12.5, "weekly": 40}
+        ]"#;
+        let_assert!(Ok(from_json) = parse_trace(json));
+        assert!(from_json == from_csv);
+    }
+
+    #[test]
+    fn test_parse_errors_name_the_line() {
+        let_assert!(Err(e) = parse_trace("2025-11-22T12:00:00Z,10\n"));
+        assert!(e == "line 1: expected timestamp,five_hour,weekly");
+        let_assert!(Err(e) = parse_trace("timestamp,five_hour,weekly\nyesterday,1,2\n"));
+        assert!(e == "line 2: invalid timestamp 'yesterday'");
+        assert!(parse_trace("").is_err());
+    }
+
+    #[test]
+    fn test_fixed_interval_latency_and_requests() {
+        let config = PollerConfig {
+            strategy: StrategyKind::Fixed,
+            fixed_interval_secs: 600,
+            ..Default::default()
+        };
+        // Increases at 15m and 25m; fetches every 10 minutes until the 1h mark
+        let trace = trace(&[
+            (0, 10.0, 40.0),
+            (900, 12.0, 40.0),
+            (1500, 12.0, 41.0),
+            (3600, 12.0, 41.0),
+        ]);
+
+        let_assert!(Ok(report) = simulate(&trace, &config));
+        assert!(report.requests == 7);
+        assert!(report.span == Duration::from_secs(3600));
+        assert!(report.latencies == [Duration::from_secs(300), Duration::from_secs(300)]);
+        assert!(report.missed == 0);
+        let total: Duration = report.time_in_state.iter().map(|(_, d)| *d).sum();
+        assert!(total == report.span);
+    }
+
+    #[test]
+    fn test_adaptive_speeds_up_during_activity() {
+        // A quiet start, then both windows climb steadily for two hours
+        let mut samples = vec![(0, 10.0, 40.0)];
+        samples.extend((1..=120).map(|i| (60 * i, 10.0 + i as f64 / 2.0, 40.0 + i as f64 / 6.0)));
+        let trace = trace(&samples);
+
+        let_assert!(Ok(report) = simulate(&trace, &PollerConfig::default()));
+        let (_, blazing) = report.time_in_state[4];
+        assert!(blazing > Duration::from_secs(3600));
+        // Polling at the minimum interval sees each increase within 3 minutes
+        assert!(report.latency_percentile(100) <= Some(Duration::from_secs(180)));
+    }
+
+    #[test]
+    fn test_format_reports_side_by_side() {
+        let trace = trace(&[(0, 10.0, 40.0), (900, 12.0, 40.0), (3600, 12.0, 40.0)]);
+        let fixed = PollerConfig {
+            strategy: StrategyKind::Fixed,
+            fixed_interval_secs: 600,
+            ..Default::default()
+        };
+        let_assert!(Ok(a) = simulate(&trace, &PollerConfig::default()));
+        let_assert!(Ok(b) = simulate(&trace, &fixed));
+
+        let table = format_reports(&[("config", &a), ("fixed.toml", &b)]);
+        let lines: Vec<&str> = table.lines().collect();
+        assert!(lines[0].trim_start().starts_with("config"));
+        assert!(lines[0].ends_with("fixed.toml"));
+        assert!(
+            lines
+                .iter()
+                .any(|l| l.starts_with("Requests") && l.ends_with(" 7"))
+        );
+        assert!(
+            lines
+                .iter()
+                .any(|l| l.starts_with("Latency max") && l.ends_with("5m"))
+        );
+    }
+}
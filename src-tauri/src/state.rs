@@ -1,6 +1,11 @@
+use crate::diagnostics::AttemptLog;
 use crate::error::FetchError;
+use crate::forecast::Forecast;
 use crate::poller::UsageMetrics;
+use crate::provider::UsageSnapshot;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 /// Represents the application's data state with error tracking and last-known-good support
 #[derive(Debug, Clone, Default)]
@@ -9,12 +14,29 @@ pub struct AppState {
     pub last_success: Option<SuccessfulFetch>,
     /// Current error state (None if no active error)
     pub current_error: Option<FetchError>,
+    /// Set when the config file failed to load and the previous (or default) config is in use
+    pub config_warning: Option<String>,
+    /// Time-to-limit projections from the latest successful fetch
+    pub forecasts: Vec<Forecast>,
+    /// Set while polling is paused from the tray menu
+    pub user_pause: Option<UserPause>,
+    /// Recent fetch attempts for the diagnostics view
+    pub attempts: AttemptLog,
+}
+
+/// A pause requested from the tray menu
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UserPause {
+    /// Paused until the user resumes
+    Indefinite,
+    /// Paused until the given wall-clock time
+    Until(std::time::SystemTime),
 }
 
 #[derive(Debug, Clone)]
 pub struct SuccessfulFetch {
     pub metrics: UsageMetrics,
-    pub usage_data: UsageData,
+    pub snapshot: UsageSnapshot,
     pub timestamp: std::time::SystemTime,
 }
 
@@ -23,11 +45,16 @@ impl AppState {
         Self::default()
     }
 
-    pub fn update_success(&mut self, metrics: UsageMetrics, usage_data: UsageData) {
+    pub fn update_success(
+        &mut self,
+        metrics: UsageMetrics,
+        snapshot: UsageSnapshot,
+        timestamp: std::time::SystemTime,
+    ) {
         self.last_success = Some(SuccessfulFetch {
             metrics,
-            usage_data,
-            timestamp: std::time::SystemTime::now(),
+            snapshot,
+            timestamp,
         });
         self.current_error = None;
     }
@@ -51,12 +78,25 @@ pub struct UsageData {
     pub five_hour: UsagePeriod,
     pub seven_day: UsagePeriod,
     pub seven_day_oauth_apps: Option<UsagePeriod>,
-    pub seven_day_opus: UsagePeriod,
+    pub seven_day_opus: Option<UsagePeriod>,
     pub iguana_necktie: Option<UsagePeriod>,
+    /// Fields the API added that aren't listed above
+    #[serde(flatten)]
+    pub other: BTreeMap<String, serde_json::Value>,
+}
+
+impl UsageData {
+    /// Unlisted fields that have the shape of a usage period
+    pub fn other_periods(&self) -> impl Iterator<Item = (&str, UsagePeriod)> {
+        self.other.iter().filter_map(|(id, value)| {
+            let period = serde_json::from_value::<UsagePeriod>(value.clone()).ok()?;
+            Some((id.as_str(), period))
+        })
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct UsagePeriod {
     pub utilization: f64,
-    pub resets_at: Option<String>,
+    pub resets_at: Option<DateTime<Utc>>,
 }
@@ -0,0 +1,482 @@
+use crate::poller::{PollerConfig, TemperatureState};
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Which `PollingStrategy` the poller uses
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StrategyKind {
+    /// Temperature-based AIMD: poll faster while usage climbs
+    #[default]
+    Adaptive,
+    /// Always wait `fixed_interval_secs`
+    Fixed,
+    /// Back off exponentially the longer usage stays unchanged
+    IdleDecay,
+    /// Spread `daily_requests` fetches evenly over each day
+    DailyBudget,
+}
+
+impl StrategyKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StrategyKind::Adaptive => "adaptive",
+            StrategyKind::Fixed => "fixed",
+            StrategyKind::IdleDecay => "idle_decay",
+            StrategyKind::DailyBudget => "daily_budget",
+        }
+    }
+}
+
+/// What a strategy sees when choosing the next interval
+#[derive(Debug, Clone, Copy)]
+pub struct StrategyInput<'a> {
+    pub config: &'a PollerConfig,
+    /// Activity state detected from the tracked history
+    pub state: TemperatureState,
+    /// Interval chosen after the previous fetch
+    pub current_interval: Duration,
+    /// Time since usage last increased (`Duration::MAX` when unknown)
+    pub idle_time: Duration,
+    pub now: Instant,
+}
+
+/// Decides how long to wait after each successful fetch
+///
+/// Parameters are read from `StrategyInput::config` on every call, so config
+/// reloads apply without rebuilding the strategy or losing its state.
+pub trait PollingStrategy: Send + Sync {
+    fn kind(&self) -> StrategyKind;
+
+    /// Interval until the next fetch, called once per successful fetch
+    fn next_interval(&mut self, input: &StrategyInput) -> Duration;
+
+    /// Wait before retrying, called once per failed fetch
+    ///
+    /// `delay` comes from the retry backoff; a strategy may only lengthen it.
+    fn retry_delay(&mut self, delay: Duration, _input: &StrategyInput) -> Duration {
+        delay
+    }
+
+    /// Whether the next fetch may be pulled forward to land just after a window reset
+    fn follows_resets(&self) -> bool {
+        true
+    }
+
+    /// Whether jitter may shorten the interval without breaking the strategy's guarantees
+    fn accepts_jitter(&self) -> bool {
+        true
+    }
+
+    /// How far back the strategy wants fetches passed to `restore`
+    fn history_window(&self) -> Duration {
+        Duration::ZERO
+    }
+
+    /// Seed from fetches made before a restart, given as their ages at `now`, oldest first
+    fn restore(&mut self, _ages: &[Duration], _now: Instant) {}
+}
+
+/// Build the strategy selected in `config`
+pub fn from_config(config: &PollerConfig) -> Box<dyn PollingStrategy> {
+    match config.strategy {
+        StrategyKind::Adaptive => Box::new(AimdStrategy),
+        StrategyKind::Fixed => Box::new(FixedStrategy),
+        StrategyKind::IdleDecay => Box::new(IdleDecayStrategy),
+        StrategyKind::DailyBudget => Box::new(DailyBudgetStrategy::default()),
+    }
+}
+
+fn clamp_to_bounds(interval: Duration, config: &PollerConfig) -> Duration {
+    interval.clamp(
+        Duration::from_secs(config.min_interval_secs),
+        Duration::from_secs(config.max_interval_secs),
+    )
+}
+
+/// The five-state temperature model with additive increase, multiplicative decrease
+///
+/// Cold and Cool grow the interval additively; Warm, Hot and Blazing shrink it
+/// by their multipliers. The result is smoothed towards the target, reacting
+/// faster the hotter the state.
+#[derive(Debug, Default)]
+pub struct AimdStrategy;
+
+impl AimdStrategy {
+    fn interval_for_state(
+        current: Duration,
+        state: TemperatureState,
+        config: &PollerConfig,
+    ) -> Duration {
+        match state {
+            TemperatureState::Cold => current + Duration::from_secs(config.additive_increase_secs),
+            TemperatureState::Cool => {
+                current + Duration::from_secs(config.additive_increase_secs / 2)
+            }
+            TemperatureState::Warm => {
+                Duration::from_secs((current.as_secs() as f64 * config.warm_multiplier) as u64)
+            }
+            TemperatureState::Hot => {
+                Duration::from_secs((current.as_secs() as f64 * config.hot_multiplier) as u64)
+            }
+            TemperatureState::Blazing => {
+                Duration::from_secs((current.as_secs() as f64 * config.blazing_multiplier) as u64)
+            }
+        }
+    }
+
+    fn smoothing_factor(state: TemperatureState) -> f64 {
+        match state {
+            TemperatureState::Blazing => 0.7,
+            TemperatureState::Hot => 0.5,
+            TemperatureState::Warm | TemperatureState::Cool | TemperatureState::Cold => 0.3,
+        }
+    }
+
+    fn apply_smoothing(current: Duration, target: Duration, factor: f64) -> Duration {
+        Duration::from_secs(
+            (current.as_secs() as f64 * (1.0 - factor) + target.as_secs() as f64 * factor) as u64,
+        )
+    }
+}
+
+impl PollingStrategy for AimdStrategy {
+    fn kind(&self) -> StrategyKind {
+        StrategyKind::Adaptive
+    }
+
+    fn next_interval(&mut self, input: &StrategyInput) -> Duration {
+        let target = clamp_to_bounds(
+            Self::interval_for_state(input.current_interval, input.state, input.config),
+            input.config,
+        );
+        Self::apply_smoothing(
+            input.current_interval,
+            target,
+            Self::smoothing_factor(input.state),
+        )
+    }
+}
+
+/// Always wait `fixed_interval_secs`, ignoring activity and resets
+#[derive(Debug, Default)]
+pub struct FixedStrategy;
+
+impl PollingStrategy for FixedStrategy {
+    fn kind(&self) -> StrategyKind {
+        StrategyKind::Fixed
+    }
+
+    fn next_interval(&mut self, input: &StrategyInput) -> Duration {
+        Duration::from_secs(input.config.fixed_interval_secs)
+    }
+
+    fn follows_resets(&self) -> bool {
+        false
+    }
+}
+
+/// Poll at `min_interval_secs` while usage changes, doubling the interval for
+/// every `idle_doubling_secs` without an increase, up to `max_interval_secs`
+#[derive(Debug, Default)]
+pub struct IdleDecayStrategy;
+
+impl PollingStrategy for IdleDecayStrategy {
+    fn kind(&self) -> StrategyKind {
+        StrategyKind::IdleDecay
+    }
+
+    fn next_interval(&mut self, input: &StrategyInput) -> Duration {
+        let config = input.config;
+        // Not enough history yet: start from the fastest interval
+        let idle = if input.idle_time == Duration::MAX {
+            Duration::ZERO
+        } else {
+            input.idle_time
+        };
+        let doublings = idle.as_secs_f64() / config.idle_doubling_secs as f64;
+        let secs = (config.min_interval_secs as f64 * doublings.exp2())
+            .min(config.max_interval_secs as f64);
+        clamp_to_bounds(Duration::from_secs_f64(secs), config)
+    }
+}
+
+/// Spend at most `daily_requests` fetches in any 24 hours, evenly spaced
+///
+/// Failed fetches count too, and retries wait as long as the budget requires.
+/// Extra fetches (manual refreshes, restarts) are paid back by waiting until
+/// the oldest fetch of the last day ages out.
+#[derive(Debug, Default)]
+pub struct DailyBudgetStrategy {
+    /// When each fetch of the last 24 hours stops counting, oldest first
+    ///
+    /// Kept as expiry times because `Instant` can't reach back a day on a
+    /// machine that booted more recently than that.
+    expires_at: VecDeque<Instant>,
+}
+
+impl DailyBudgetStrategy {
+    /// Count a fetch made at `now` and return the wait that keeps within the budget
+    fn record(&mut self, config: &PollerConfig, now: Instant) -> Duration {
+        let budget = config.daily_requests.max(1) as usize;
+        self.expires_at.push_back(now + DAY);
+        while self.expires_at.front().is_some_and(|at| *at <= now) {
+            self.expires_at.pop_front();
+        }
+
+        let spacing = DAY / budget as u32;
+        if self.expires_at.len() < budget {
+            return spacing;
+        }
+        // Over budget: wait until enough fetches fall out of the window
+        self.expires_at[self.expires_at.len() - budget]
+            .saturating_duration_since(now)
+            .max(spacing)
+    }
+}
+
+impl PollingStrategy for DailyBudgetStrategy {
+    fn kind(&self) -> StrategyKind {
+        StrategyKind::DailyBudget
+    }
+
+    fn next_interval(&mut self, input: &StrategyInput) -> Duration {
+        self.record(input.config, input.now)
+    }
+
+    fn retry_delay(&mut self, delay: Duration, input: &StrategyInput) -> Duration {
+        delay.max(self.record(input.config, input.now))
+    }
+
+    fn follows_resets(&self) -> bool {
+        false
+    }
+
+    // A shortened interval could go over the budget
+    fn accepts_jitter(&self) -> bool {
+        false
+    }
+
+    fn history_window(&self) -> Duration {
+        DAY
+    }
+
+    fn restore(&mut self, ages: &[Duration], now: Instant) {
+        self.expires_at.extend(
+            ages.iter()
+                .filter_map(|age| DAY.checked_sub(*age))
+                .map(|left| now + left),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::assert;
+    use rstest::rstest;
+
+    fn input(
+        config: &PollerConfig,
+        state: TemperatureState,
+        current: Duration,
+    ) -> StrategyInput<'_> {
+        StrategyInput {
+            config,
+            state,
+            current_interval: current,
+            idle_time: Duration::MAX,
+            now: Instant::now(),
+        }
+    }
+
+    #[rstest]
+    #[case(TemperatureState::Cold, 0.3)]
+    #[case(TemperatureState::Cool, 0.3)]
+    #[case(TemperatureState::Warm, 0.3)]
+    #[case(TemperatureState::Hot, 0.5)]
+    #[case(TemperatureState::Blazing, 0.7)]
+    fn test_smoothing_factors(#[case] state: TemperatureState, #[case] expected: f64) {
+        let factor = AimdStrategy::smoothing_factor(state);
+        assert!(factor == expected);
+    }
+
+    #[rstest]
+    #[case(
+        Duration::from_secs(100),
+        Duration::from_secs(200),
+        0.5,
+        Duration::from_secs(150)
+    )]
+    #[case(
+        Duration::from_secs(100),
+        Duration::from_secs(300),
+        0.3,
+        Duration::from_secs(160)
+    )]
+    #[case(
+        Duration::from_secs(500),
+        Duration::from_secs(100),
+        0.7,
+        Duration::from_secs(220)
+    )]
+    fn test_smoothing_application(
+        #[case] current: Duration,
+        #[case] target: Duration,
+        #[case] factor: f64,
+        #[case] expected: Duration,
+    ) {
+        let result = AimdStrategy::apply_smoothing(current, target, factor);
+        assert!(result == expected);
+    }
+
+    #[test]
+    fn test_aimd_target_clamped_to_min() {
+        let config = PollerConfig::default();
+        let current = Duration::from_secs(10); // Very low
+        let interval =
+            AimdStrategy.next_interval(&input(&config, TemperatureState::Blazing, current));
+
+        // Smoothed towards the clamped target, so it moves up from `current`
+        assert!(interval > current);
+        let target = AimdStrategy::interval_for_state(current, TemperatureState::Blazing, &config);
+        assert!(clamp_to_bounds(target, &config) == Duration::from_secs(config.min_interval_secs));
+    }
+
+    #[test]
+    fn test_aimd_target_clamped_to_max() {
+        let config = PollerConfig::default();
+        let current = Duration::from_secs(10000); // Very high
+        let interval = AimdStrategy.next_interval(&input(&config, TemperatureState::Cold, current));
+
+        assert!(interval < current);
+        let target = AimdStrategy::interval_for_state(current, TemperatureState::Cold, &config);
+        assert!(clamp_to_bounds(target, &config) == Duration::from_secs(config.max_interval_secs));
+    }
+
+    #[rstest]
+    #[case(TemperatureState::Cold)]
+    #[case(TemperatureState::Blazing)]
+    fn test_fixed_ignores_activity(#[case] state: TemperatureState) {
+        let config = PollerConfig {
+            fixed_interval_secs: 900,
+            ..Default::default()
+        };
+        let interval =
+            FixedStrategy.next_interval(&input(&config, state, Duration::from_secs(180)));
+        assert!(interval == Duration::from_secs(900));
+    }
+
+    #[rstest]
+    #[case(None, 180)]
+    #[case(Some(0), 180)]
+    #[case(Some(900), 360)]
+    #[case(Some(1800), 720)]
+    // Capped at max_interval_secs
+    #[case(Some(8 * 3600), 5400)]
+    fn test_idle_decay_doubles(#[case] idle_secs: Option<u64>, #[case] expected_secs: u64) {
+        let config = PollerConfig {
+            idle_doubling_secs: 900,
+            ..Default::default()
+        };
+        let input = StrategyInput {
+            idle_time: idle_secs.map_or(Duration::MAX, Duration::from_secs),
+            ..input(&config, TemperatureState::Cold, Duration::from_secs(180))
+        };
+        let interval = IdleDecayStrategy.next_interval(&input);
+        assert!(interval == Duration::from_secs(expected_secs));
+    }
+
+    #[test]
+    fn test_daily_budget_spreads_requests() {
+        let config = PollerConfig {
+            daily_requests: 48,
+            ..Default::default()
+        };
+        let mut strategy = DailyBudgetStrategy::default();
+        let start = Instant::now();
+
+        // Activity doesn't matter; the spacing is a constant 30 minutes
+        let mut now = start;
+        let mut fetches = 0;
+        while now < start + DAY {
+            let interval = strategy.next_interval(&StrategyInput {
+                now,
+                ..input(&config, TemperatureState::Blazing, Duration::from_secs(180))
+            });
+            assert!(interval == Duration::from_secs(1800));
+            now += interval;
+            fetches += 1;
+        }
+        assert!(fetches == 48);
+    }
+
+    #[test]
+    fn test_daily_budget_pays_back_extra_fetches() {
+        let config = PollerConfig {
+            daily_requests: 4,
+            ..Default::default()
+        };
+        let mut strategy = DailyBudgetStrategy::default();
+        let now = Instant::now();
+        // Three fetches restored from before a restart, an hour apart, and
+        // one from more than a day ago that no longer counts
+        strategy.restore(
+            &[25, 3, 2, 1].map(|hours| Duration::from_secs(hours * 3600)),
+            now,
+        );
+
+        let interval = strategy.next_interval(&StrategyInput {
+            now,
+            ..input(&config, TemperatureState::Cold, Duration::from_secs(180))
+        });
+        // Budget spent: wait until the first fetch is a day old
+        assert!(interval == Duration::from_secs(21 * 3600));
+    }
+
+    #[test]
+    fn test_daily_budget_counts_failed_fetches() {
+        let config = PollerConfig {
+            daily_requests: 4,
+            ..Default::default()
+        };
+        let mut strategy = DailyBudgetStrategy::default();
+        let start = Instant::now();
+        let retry = Duration::from_secs(5);
+
+        // Retries are spaced like regular fetches instead of using the backoff
+        let mut now = start;
+        for _ in 0..3 {
+            let delay = strategy.retry_delay(
+                retry,
+                &StrategyInput {
+                    now,
+                    ..input(&config, TemperatureState::Cold, Duration::from_secs(180))
+                },
+            );
+            assert!(delay == Duration::from_secs(6 * 3600));
+            now += retry;
+        }
+
+        // The success after them spends the last of the budget
+        let interval = strategy.next_interval(&StrategyInput {
+            now,
+            ..input(&config, TemperatureState::Cold, Duration::from_secs(180))
+        });
+        assert!(interval == start + DAY - now);
+
+        // A long backoff is kept as-is
+        let long = Duration::from_secs(30 * 3600);
+        let delay = strategy.retry_delay(
+            long,
+            &StrategyInput {
+                now,
+                ..input(&config, TemperatureState::Cold, Duration::from_secs(180))
+            },
+        );
+        assert!(delay == long);
+    }
+}
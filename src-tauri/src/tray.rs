@@ -1,78 +1,445 @@
-use crate::error::ErrorIndicator;
-use crate::icon::{STALENESS_THRESHOLD_SECS, generate_unknown_icon, generate_usage_icon};
-use crate::poller::AdaptivePoller;
+use crate::breaker::{BreakerState, CircuitBreaker};
+use crate::commands::{CommandSender, PollCommand};
+use crate::config::env_var_for;
+use crate::diagnostics::{AttemptLog, FetchAttempt};
+use crate::error::{ErrorIndicator, FetchError};
+use crate::forecast::describe_reset;
+use crate::icon::{IconConfig, generate_unknown_icon, generate_usage_icon};
+use crate::poller::{AdaptivePoller, Permille};
+use crate::provider::{MetricRole, UsageProvider, UsageSnapshot, fallback_label};
 use crate::retry::RetryState;
-use crate::state::AppState;
-use std::time::SystemTime;
-use tauri::AppHandle;
+use crate::state::{AppState, UsagePeriod, UserPause};
+use chrono::{DateTime, Local, TimeZone, Utc};
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+use tauri::menu::{Menu, MenuBuilder, MenuItemBuilder, SubmenuBuilder};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_opener::OpenerExt;
+use tracing::{error, info, warn};
 
-pub fn update_tray_icon(
+pub const MENU_REFRESH: &str = "refresh";
+pub const MENU_PAUSE_HOUR: &str = "pause_hour";
+pub const MENU_PAUSE: &str = "pause";
+pub const MENU_RESUME: &str = "resume";
+pub const MENU_RETRY: &str = "retry";
+pub const MENU_OPEN_CONFIG: &str = "open_config";
+pub const MENU_RELOAD_CONFIG: &str = "reload_config";
+pub const MENU_COPY_STATUS: &str = "copy_status";
+pub const MENU_EXPORT_DIAGNOSTICS: &str = "export_diagnostics";
+pub const MENU_QUIT: &str = "quit";
+
+/// Duration of the "Pause polling for 1h" action
+const SHORT_PAUSE: Duration = Duration::from_secs(60 * 60);
+
+/// Longest error message shown in the menu before truncating
+const MAX_MENU_ERROR_LEN: usize = 60;
+
+/// Fetch attempts listed in the Diagnostics submenu (all are exported)
+const DIAGNOSTIC_ROWS: usize = 10;
+
+const DIAGNOSTICS_FILE_NAME: &str = "diagnostics.json";
+
+const CONFIG_TEMPLATE: &str =
+    "# usage-indicator configuration\n# See the README for available keys.\n";
+
+/// Config file opened by the "Open config" action
+pub struct ConfigPath(pub Option<PathBuf>);
+
+/// Latest status text, copied by the "Copy status to clipboard" action
+#[derive(Default)]
+pub struct StatusText(pub Mutex<String>);
+
+/// Latest fetch attempts, written by the "Export as JSON" diagnostics action
+#[derive(Default)]
+pub struct Diagnostics(pub Mutex<AttemptLog>);
+
+fn format_time(time: SystemTime) -> String {
+    DateTime::<Local>::from(time).format("%H:%M").to_string()
+}
+
+/// Utilization with `decimals` decimal places (e.g. "42%", "42.3%"), marking overage
+fn format_percent(utilization: f64, decimals: u8) -> String {
+    // Round half away from zero like the icon and notifications; `{:.0}` rounds half to even
+    let scale = 10f64.powi(i32::from(decimals));
+    let rounded = (utilization * scale).round() / scale;
+    let text = format!("{:.*}%", usize::from(decimals), rounded);
+    if Permille::from_pct(utilization).is_over_limit() {
+        format!("{} (over limit)", text)
+    } else {
+        text
+    }
+}
+
+/// Tooltip text for the current error: how to fix a config problem, or when the next retry is
+fn error_details(
+    error: &FetchError,
+    retry_delay: Duration,
+    breaker: BreakerState,
+    config_path: Option<&Path>,
+) -> String {
+    match error {
+        FetchError::Config { key, .. } => {
+            let file = config_path.map_or_else(
+                || "the config file".to_string(),
+                |path| path.display().to_string(),
+            );
+            // The error already names the variable; the file watcher can't see it change
+            let env = env_var_for(key)
+                .map(|_| "\nEnvironment variables are only read at startup and need a restart.")
+                .unwrap_or_default();
+            format!(
+                "⚠ {}: {}\nSet {} in {} and polling resumes when the file is saved.{}",
+                error.category(),
+                error,
+                key,
+                file,
+                env
+            )
+        }
+        _ if breaker != BreakerState::Closed => format!(
+            "⚠ {}: {}\nCircuit breaker {} after repeated failures\nNext probe in: {}s",
+            error.category(),
+            error,
+            breaker.as_str(),
+            retry_delay.as_secs()
+        ),
+        _ => format!(
+            "⚠ {}: {}\nRetry in: {}s",
+            error.category(),
+            error,
+            retry_delay.as_secs()
+        ),
+    }
+}
+
+fn truncate(text: &str, max_len: usize) -> String {
+    if text.chars().count() <= max_len {
+        text.to_string()
+    } else {
+        let truncated: String = text.chars().take(max_len - 1).collect();
+        format!("{}…", truncated)
+    }
+}
+
+/// Diagnostics row for one attempt, e.g. "14:02:11 auth, HTTP 401 (95 ms), retry in 5s"
+fn attempt_row<Tz: TimeZone>(attempt: &FetchAttempt, tz: &Tz) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    let time = attempt.started_at.with_timezone(tz).format("%H:%M:%S");
+    let took = attempt.duration.as_millis();
+    let status = attempt
+        .http_status
+        .map(|code| format!(", HTTP {}", code))
+        .unwrap_or_default();
+    let Some(kind) = &attempt.error_kind else {
+        return format!("{} OK{} ({} ms)", time, status, took);
+    };
+
+    let retry = attempt
+        .retry_delay
+        .map(|delay| format!(", retry in {}s", delay.as_secs()))
+        .unwrap_or_default();
+    format!("{} {}{} ({} ms){}", time, kind, status, took, retry)
+}
+
+fn pause_row(pause: UserPause) -> String {
+    match pause {
+        UserPause::Indefinite => "Paused until resumed".to_string(),
+        UserPause::Until(until) => format!("Paused until {}", format_time(until)),
+    }
+}
+
+/// Build the tray menu: disabled status rows followed by actions
+pub fn build_menu(
+    app: &AppHandle,
+    status_rows: &[String],
+    diagnostic_rows: &[String],
+    pause: Option<UserPause>,
+    has_error: bool,
+) -> tauri::Result<Menu<tauri::Wry>> {
+    let mut builder = MenuBuilder::new(app);
+    for (index, row) in status_rows.iter().enumerate() {
+        let item = MenuItemBuilder::with_id(format!("status_{}", index), row)
+            .enabled(false)
+            .build(app)?;
+        builder = builder.item(&item);
+    }
+
+    builder = builder.separator().text(MENU_REFRESH, "Refresh now");
+    if has_error {
+        builder = builder.text(MENU_RETRY, "Retry now (reset backoff)");
+    }
+    builder = if pause.is_some() {
+        builder.text(MENU_RESUME, "Resume polling")
+    } else {
+        builder
+            .text(MENU_PAUSE_HOUR, "Pause polling for 1h")
+            .text(MENU_PAUSE, "Pause polling until resumed")
+    };
+
+    let mut diagnostics = SubmenuBuilder::new(app, "Diagnostics");
+    if diagnostic_rows.is_empty() {
+        diagnostics = diagnostics.item(
+            &MenuItemBuilder::with_id("attempt_none", "No fetches yet")
+                .enabled(false)
+                .build(app)?,
+        );
+    }
+    for (index, row) in diagnostic_rows.iter().enumerate() {
+        let item = MenuItemBuilder::with_id(format!("attempt_{}", index), row)
+            .enabled(false)
+            .build(app)?;
+        diagnostics = diagnostics.item(&item);
+    }
+    let diagnostics = diagnostics
+        .separator()
+        .text(MENU_EXPORT_DIAGNOSTICS, "Export as JSON")
+        .build()?;
+
+    builder
+        .separator()
+        .text(MENU_OPEN_CONFIG, "Open config")
+        .text(MENU_RELOAD_CONFIG, "Reload config")
+        .text(MENU_COPY_STATUS, "Copy status to clipboard")
+        .item(&diagnostics)
+        .separator()
+        .text(MENU_QUIT, "Quit")
+        .build()
+}
+
+/// (label, id, period) for every metric in the snapshot
+///
+/// Described metrics come first in the provider's order, followed by any
+/// buckets the provider doesn't know about yet.
+fn metric_periods<'a, P: UsageProvider>(
+    provider: &'a P,
+    snapshot: &'a UsageSnapshot,
+) -> impl Iterator<Item = (Cow<'a, str>, &'a str, &'a UsagePeriod)> {
+    let described = provider.metrics().iter().filter_map(|descriptor| {
+        let period = snapshot.get(descriptor.id)?;
+        Some((Cow::Borrowed(descriptor.label), descriptor.id, period))
+    });
+    let undescribed = snapshot
+        .undescribed(provider.metrics())
+        .map(|(id, period)| (Cow::Owned(fallback_label(id)), id, period));
+    described.chain(undescribed)
+}
+
+/// Disabled rows summarizing the current state for the menu
+fn status_rows<P: UsageProvider>(
+    provider: &P,
+    state: &AppState,
+    poller: &AdaptivePoller,
+    decimals: u8,
+) -> Vec<String> {
+    let mut rows = Vec::new();
+
+    match &state.last_success {
+        Some(success) => {
+            for (label, _, period) in metric_periods(provider, &success.snapshot) {
+                rows.push(format!(
+                    "{}: {}",
+                    label,
+                    format_percent(period.utilization, decimals)
+                ));
+            }
+            rows.push(format!("Updated: {}", format_time(success.timestamp)));
+        }
+        None => rows.push("No data available yet".to_string()),
+    }
+
+    rows.push(format!("State: {:?}", poller.current_state()));
+    if let Some(pause) = state.user_pause {
+        rows.push(pause_row(pause));
+    }
+    if let Some(error) = &state.current_error {
+        rows.push(truncate(
+            &format!("Last error: {}", error),
+            MAX_MENU_ERROR_LEN,
+        ));
+    }
+
+    rows
+}
+
+/// Dispatch a tray menu click
+pub fn handle_menu_event(app: &AppHandle, id: &str) {
+    let command = match id {
+        MENU_REFRESH => PollCommand::RefreshNow,
+        MENU_PAUSE_HOUR => PollCommand::Pause(Some(SHORT_PAUSE)),
+        MENU_PAUSE => PollCommand::Pause(None),
+        MENU_RESUME => PollCommand::Resume,
+        MENU_RETRY => PollCommand::ResetBackoff,
+        MENU_RELOAD_CONFIG => PollCommand::ReloadConfig,
+        MENU_OPEN_CONFIG => {
+            if let Err(e) = open_config(app) {
+                error!("Failed to open config file: {}", e);
+            }
+            return;
+        }
+        MENU_COPY_STATUS => {
+            let status = app.state::<StatusText>().0.lock().unwrap().clone();
+            if let Err(e) = app.clipboard().write_text(status) {
+                error!("Failed to copy status to clipboard: {}", e);
+            }
+            return;
+        }
+        MENU_EXPORT_DIAGNOSTICS => {
+            if let Err(e) = export_diagnostics(app) {
+                error!("Failed to export diagnostics: {}", e);
+            }
+            return;
+        }
+        MENU_QUIT => {
+            app.exit(0);
+            return;
+        }
+        _ => return,
+    };
+
+    if app.state::<CommandSender>().send(command).is_err() {
+        warn!(?command, "Polling loop has stopped, ignoring command");
+    }
+}
+
+/// Open the config file in the default editor, creating it if needed
+fn open_config(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+    let path = app
+        .state::<ConfigPath>()
+        .0
+        .clone()
+        .ok_or("No config file location available")?;
+
+    if !path.exists() {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, CONFIG_TEMPLATE)?;
+    }
+
+    app.opener()
+        .open_path(path.to_string_lossy(), None::<&str>)?;
+    Ok(())
+}
+
+/// Write recent fetch attempts to a JSON file and open it
+fn export_diagnostics(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+    let path = dirs::data_dir()
+        .ok_or("No data directory available")?
+        .join("usage-indicator")
+        .join(DIAGNOSTICS_FILE_NAME);
+    let json = app.state::<Diagnostics>().0.lock().unwrap().to_json()?;
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, json)?;
+    info!(path = %path.display(), "Exported diagnostics");
+
+    app.opener()
+        .open_path(path.to_string_lossy(), None::<&str>)?;
+    Ok(())
+}
+
+pub fn update_tray_icon<P: UsageProvider>(
     app: &AppHandle,
+    provider: &P,
+    icon_config: &IconConfig,
     state: &AppState,
     poller: &AdaptivePoller,
     retry_state: &RetryState,
+    breaker: &CircuitBreaker,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let tray = app.tray_by_id("main").ok_or("Tray not found")?;
 
+    let config_path = app.state::<ConfigPath>().0.clone();
+
     // Determine error indicator from current error
     let error_indicator = ErrorIndicator::from_error(state.current_error.as_ref());
 
     // Generate icon based on state
     let icon_bytes = if let Some(success) = &state.last_success {
-        generate_usage_icon(success.metrics.weekly_pct(), error_indicator)
+        // Use the configured metric, falling back to the long window metric
+        let configured = icon_config
+            .metric
+            .as_deref()
+            .and_then(|id| success.snapshot.get(id));
+        let usage = configured
+            .or_else(|| {
+                success
+                    .snapshot
+                    .by_role(provider.metrics(), MetricRole::LongWindow)
+                    .map(|(_, period)| period)
+            })
+            .map(|period| Permille::from_pct(period.utilization))
+            .unwrap_or(success.metrics.weekly());
+        generate_usage_icon(usage, error_indicator)
     } else {
-        generate_unknown_icon()
+        generate_unknown_icon(error_indicator)
     };
 
     let icon = tauri::image::Image::new_owned(icon_bytes, 32, 32);
     tray.set_icon(Some(icon))?;
 
     // Build comprehensive tooltip
-    let tooltip = if let Some(success) = &state.last_success {
+    let mut tooltip = if let Some(success) = &state.last_success {
         let elapsed = SystemTime::now()
             .duration_since(success.timestamp)
             .map(|d| d.as_secs())
             .unwrap_or(0);
 
-        let format_reset_time = |resets_at: &Option<String>| -> String {
+        let now = Utc::now();
+        let format_reset = |resets_at: Option<DateTime<Utc>>| -> String {
             resets_at
-                .as_ref()
-                .map(|s| {
-                    s.split('T')
-                        .next()
-                        .map(|date| date.to_string())
-                        .unwrap_or_else(|| s.clone())
-                })
-                .unwrap_or_else(|| "Unknown".to_string())
+                .map(|resets_at| describe_reset(resets_at, now, &Local))
+                .unwrap_or_else(|| "reset time unknown".to_string())
         };
 
-        let mut tooltip = format!(
-            "Claude Usage Indicator\n\
-            \n\
-            Weekly: {}% (resets {})\n\
-            5-hour: {}% (resets {})\n\
-            \n\
+        let mut tooltip = format!("{} Usage Indicator\n", provider.display_name());
+
+        for (label, id, period) in metric_periods(provider, &success.snapshot) {
+            tooltip.push_str(&format!(
+                "\n{}: {} ({})",
+                label,
+                format_percent(period.utilization, icon_config.decimals),
+                format_reset(period.resets_at)
+            ));
+
+            let forecast_line = state
+                .forecasts
+                .iter()
+                .find(|f| f.metric_id == id)
+                .and_then(|f| f.describe());
+            if let Some(line) = forecast_line {
+                tooltip.push_str(&format!("\n  {}", line));
+            }
+        }
+
+        tooltip.push_str(&format!(
+            "\n\n\
             State: {:?}\n\
             Next poll: {}s\n\
             Last update: {}s ago",
-            success.metrics.weekly_pct(),
-            format_reset_time(&success.usage_data.seven_day.resets_at),
-            success.metrics.five_hour_pct(),
-            format_reset_time(&success.usage_data.five_hour.resets_at),
             poller.current_state(),
             poller.current_interval().as_secs(),
             elapsed
-        );
+        ));
 
         // Add error information if present
         if let Some(error) = &state.current_error {
-            let is_stale = state.is_stale(STALENESS_THRESHOLD_SECS);
+            let is_stale = state.is_stale(icon_config.staleness_threshold_secs);
             tooltip.push_str(&format!(
-                "\n\n⚠ {}: {}\n\
-                Retry in: {}s{}",
-                error.category(),
-                error,
-                retry_state.current_delay().as_secs(),
+                "\n\n{}{}",
+                error_details(
+                    error,
+                    breaker.delay(retry_state.last_delay()),
+                    breaker.state(),
+                    config_path.as_deref()
+                ),
                 if is_stale { " (data is stale)" } else { "" }
             ));
         }
@@ -80,15 +447,20 @@ pub fn update_tray_icon(
         tooltip
     } else {
         // No data available yet
-        let mut tooltip = String::from("Claude Usage Indicator\n\nStatus: No data available yet");
+        let mut tooltip = format!(
+            "{} Usage Indicator\n\nStatus: No data available yet",
+            provider.display_name()
+        );
 
         if let Some(error) = &state.current_error {
             tooltip.push_str(&format!(
-                "\n\n⚠ {}: {}\n\
-                Retry in: {}s",
-                error.category(),
-                error,
-                retry_state.current_delay().as_secs()
+                "\n\n{}",
+                error_details(
+                    error,
+                    breaker.delay(retry_state.last_delay()),
+                    breaker.state(),
+                    config_path.as_deref()
+                )
             ));
         } else {
             tooltip.push_str(&format!(
@@ -100,7 +472,123 @@ pub fn update_tray_icon(
         tooltip
     };
 
+    if let Some(pause) = state.user_pause {
+        tooltip.push_str(&format!("\n\n{}", pause_row(pause)));
+    }
+
+    if let Some(warning) = &state.config_warning {
+        tooltip.push_str(&format!("\n\n⚠ Config not applied: {}", warning));
+    }
+
+    let menu = build_menu(
+        app,
+        &status_rows(provider, state, poller, icon_config.decimals),
+        &state
+            .attempts
+            .iter()
+            .rev()
+            .take(DIAGNOSTIC_ROWS)
+            .map(|attempt| attempt_row(attempt, &Local))
+            .collect::<Vec<_>>(),
+        state.user_pause,
+        state.current_error.is_some(),
+    )?;
+    tray.set_menu(Some(menu))?;
+
+    *app.state::<StatusText>().0.lock().unwrap() = tooltip.clone();
+    *app.state::<Diagnostics>().0.lock().unwrap() = state.attempts.clone();
     tray.set_tooltip(Some(tooltip))?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::NetworkErrorKind;
+    use assert2::assert;
+
+    #[test]
+    fn test_truncate_long_error() {
+        assert!(truncate("short", 10) == "short");
+        assert!(truncate("Network error: timed out", 10) == "Network e…");
+    }
+
+    #[test]
+    fn test_error_details() {
+        let config = FetchError::missing_config("credentials.org_id");
+        assert!(
+            error_details(
+                &config,
+                Duration::from_secs(5),
+                BreakerState::Closed,
+                Some(Path::new("/home/me/config.toml"))
+            ) == "⚠ Not Configured: Config error: credentials.org_id is not set in the config \
+                  file or CLAUDE_ORG_ID\n\
+                  Set credentials.org_id in /home/me/config.toml and polling resumes when the \
+                  file is saved.\n\
+                  Environment variables are only read at startup and need a restart."
+        );
+
+        let auth = FetchError::auth("Session expired");
+        assert!(
+            error_details(&auth, Duration::from_secs(5), BreakerState::Closed, None)
+                == "⚠ Authentication Error: Auth error: Session expired\nRetry in: 5s"
+        );
+        assert!(
+            error_details(&auth, Duration::from_secs(1800), BreakerState::Open, None)
+                == "⚠ Authentication Error: Auth error: Session expired\n\
+                    Circuit breaker open after repeated failures\n\
+                    Next probe in: 1800s"
+        );
+    }
+
+    #[test]
+    fn test_format_percent() {
+        assert!(format_percent(42.26, 0) == "42%");
+        assert!(format_percent(42.26, 1) == "42.3%");
+        assert!(format_percent(0.04, 1) == "0.0%");
+        assert!(format_percent(100.0, 0) == "100%");
+        assert!(format_percent(42.5, 0) == "43%");
+        assert!(format_percent(0.5, 0) == "1%");
+        assert!(format_percent(42.25, 1) == "42.3%");
+        assert!(format_percent(112.4, 0) == "112% (over limit)");
+        // Over the limit by the same measure as the icon's "100+"
+        assert!(format_percent(100.4, 0) == "100% (over limit)");
+        assert!(format_percent(100.04, 1) == "100.0%");
+    }
+
+    #[test]
+    fn test_attempt_row() {
+        let started_at: DateTime<Utc> = "2025-11-22T14:02:11Z".parse().unwrap();
+        let success = FetchAttempt::success(started_at, Duration::from_millis(812), Some(200));
+        assert!(attempt_row(&success, &Utc) == "14:02:11 OK, HTTP 200 (812 ms)");
+
+        let auth = FetchError::Auth {
+            status: Some(401),
+            message: "Invalid session key".to_string(),
+        };
+        let failure = FetchAttempt::failure(
+            started_at,
+            Duration::from_millis(95),
+            &auth,
+            Duration::from_secs(5),
+        );
+        assert!(attempt_row(&failure, &Utc) == "14:02:11 auth, HTTP 401 (95 ms), retry in 5s");
+
+        let timeout = FetchError::network(NetworkErrorKind::Timeout, "timed out");
+        let failure = FetchAttempt::failure(
+            started_at,
+            Duration::from_secs(30),
+            &timeout,
+            Duration::from_secs(10),
+        );
+        assert!(attempt_row(&failure, &Utc) == "14:02:11 timeout (30000 ms), retry in 10s");
+    }
+
+    #[test]
+    fn test_pause_row() {
+        assert!(pause_row(UserPause::Indefinite) == "Paused until resumed");
+        assert!(pause_row(UserPause::Until(SystemTime::now())).starts_with("Paused until "));
+    }
+}
@@ -1,7 +1,86 @@
 // Common test utilities and fixtures
 
+// Each test binary only uses some of these
+#![allow(dead_code)]
+
+use assert2::{assert, let_assert};
+use chrono::{DateTime, Utc};
 use mockito::{Mock, Server, ServerGuard};
 use serde_json::json;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+use usage_indicator_lib::{
+    ClaudeProvider, Clock, CredentialsConfig, Effect, PollingEngine, UsageProvider,
+};
+
+/// Clock that only moves when a test advances it
+///
+/// Engine tests skip the delays between fetches instead of waiting them out,
+/// which also works when the fetches are real requests to a mock server.
+#[derive(Clone)]
+pub struct TestClock {
+    start: Instant,
+    offset: Arc<Mutex<Duration>>,
+}
+
+impl Default for TestClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TestClock {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            offset: Arc::default(),
+        }
+    }
+
+    pub fn advance(&self, by: Duration) {
+        *self.offset.lock().unwrap() += by;
+    }
+
+    /// Move forward to `at`; the clock never goes back
+    pub fn advance_to(&self, at: Instant) {
+        let mut offset = self.offset.lock().unwrap();
+        *offset = (*offset).max(at.saturating_duration_since(self.start));
+    }
+}
+
+impl Clock for TestClock {
+    fn now(&self) -> Instant {
+        self.start + *self.offset.lock().unwrap()
+    }
+
+    fn wall_now(&self) -> DateTime<Utc> {
+        let start: DateTime<Utc> = "2025-11-22T12:00:00Z".parse().unwrap();
+        start + *self.offset.lock().unwrap()
+    }
+}
+
+/// Jump to the engine's next deadline and wake it
+pub async fn step<P: UsageProvider>(engine: &mut PollingEngine<P, TestClock>) -> Vec<Effect> {
+    let_assert!(Some(at) = engine.next_wake());
+    engine.clock().advance_to(at);
+    let effects = engine.wake().await;
+    assert!(effects.last() == Some(&Effect::SleepUntil(engine.next_wake())));
+    effects
+}
+
+/// Credentials the mock API accepts
+pub fn credentials(mock_api: &MockClaudeApi) -> CredentialsConfig {
+    CredentialsConfig {
+        org_id: Some(mock_api.org_id.clone()),
+        session_key: Some("test-session-key".to_string()),
+    }
+}
+
+/// Claude provider pointed at the mock API with working credentials
+pub fn provider(mock_api: &MockClaudeApi) -> ClaudeProvider {
+    ClaudeProvider::with_base_url(mock_api.url(), credentials(mock_api))
+}
 
 /// Mock Claude API server for testing
 pub struct MockClaudeApi {
@@ -43,6 +122,11 @@ impl MockClaudeApi {
             "iguana_necktie": null
         });
 
+        self.mock_usage_body(body)
+    }
+
+    /// Create a mock for a successful response with an arbitrary body
+    pub fn mock_usage_body(&mut self, body: serde_json::Value) -> Mock {
         self.server
             .mock(
                 "GET",
@@ -93,6 +177,21 @@ impl MockClaudeApi {
             .create()
     }
 
+    /// Create a mock for 429 rate limit error carrying an extra header
+    /// (e.g. `Retry-After` or a rate-limit reset header)
+    pub fn mock_rate_limit_with_header(&mut self, name: &str, value: &str) -> Mock {
+        self.server
+            .mock(
+                "GET",
+                format!("/api/organizations/{}/usage", self.org_id).as_str(),
+            )
+            .with_status(429)
+            .with_header("content-type", "application/json")
+            .with_header(name, value)
+            .with_body(json!({"error": "Too many requests"}).to_string())
+            .create()
+    }
+
     /// Create a mock for network/server error (5xx)
     pub fn mock_server_error(&mut self) -> Mock {
         let body = json!({
@@ -0,0 +1,536 @@
+// Scenario tests for the polling engine on a manual clock
+
+mod common;
+
+use assert2::{assert, let_assert};
+use common::{TestClock, step};
+use std::collections::{BTreeMap, VecDeque};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+use usage_indicator_lib::{
+    AppConfig, BreakerState, Clock, ConfigLoader, Effect, EngineInput, FetchError, FetchRecord,
+    HistoryStore, JitterKind, MetricDescriptor, MetricRole, NetworkErrorKind, PollCommand,
+    PollingEngine, StrategyKind, SystemEvent, TemperatureState, UsagePeriod, UsageProvider,
+    UsageSnapshot, UserPause,
+};
+
+const METRICS: &[MetricDescriptor] = &[
+    MetricDescriptor {
+        id: "five_hour",
+        label: "5-hour",
+        role: MetricRole::ShortWindow,
+    },
+    MetricDescriptor {
+        id: "seven_day",
+        label: "Weekly",
+        role: MetricRole::LongWindow,
+    },
+];
+
+type Response = Result<UsageSnapshot, FetchError>;
+
+/// Provider that replays queued responses and records when it was called
+#[derive(Clone, Default)]
+struct ScriptedProvider {
+    responses: Arc<Mutex<VecDeque<Response>>>,
+    fetched_at: Arc<Mutex<Vec<Instant>>>,
+    /// Clock the engine runs on, for the fetch times
+    clock: TestClock,
+}
+
+impl ScriptedProvider {
+    fn push(&self, response: Response) {
+        self.responses.lock().unwrap().push_back(response);
+    }
+
+    fn fetch_times(&self) -> Vec<Instant> {
+        self.fetched_at.lock().unwrap().clone()
+    }
+}
+
+impl UsageProvider for ScriptedProvider {
+    fn id(&self) -> &'static str {
+        "scripted"
+    }
+
+    fn display_name(&self) -> &str {
+        "Scripted"
+    }
+
+    fn metrics(&self) -> &[MetricDescriptor] {
+        METRICS
+    }
+
+    fn fetch(&self) -> impl Future<Output = Response> + Send {
+        self.fetched_at.lock().unwrap().push(self.clock.now());
+        let response = self
+            .responses
+            .lock()
+            .unwrap()
+            .pop_front()
+            .expect("unexpected fetch");
+        async move { response }
+    }
+}
+
+fn usage(five_hour: f64, weekly: f64) -> Response {
+    let period = |utilization| UsagePeriod {
+        utilization,
+        resets_at: Some("2025-11-29T00:00:00Z".parse().unwrap()),
+    };
+    Ok(UsageSnapshot {
+        periods: BTreeMap::from([
+            ("five_hour".to_string(), period(five_hour)),
+            ("seven_day".to_string(), period(weekly)),
+        ]),
+        http_status: Some(200),
+    })
+}
+
+fn rate_limited(retry_after: Option<u64>) -> Response {
+    Err(FetchError::RateLimited {
+        message: "Too many requests".to_string(),
+        retry_after,
+    })
+}
+
+fn engine(provider: &ScriptedProvider) -> PollingEngine<ScriptedProvider, TestClock> {
+    PollingEngine::new(
+        provider.clone(),
+        provider.clock.clone(),
+        AppConfig::default(),
+    )
+}
+
+/// Gaps between consecutive fetches
+fn gaps(times: &[Instant]) -> Vec<Duration> {
+    times.windows(2).map(|w| w[1] - w[0]).collect()
+}
+
+#[tokio::test]
+async fn test_burst_of_activity_speeds_up_polling() {
+    let provider = ScriptedProvider::default();
+    let mut engine = engine(&provider);
+
+    // Idle for a while, then both windows climb quickly
+    for _ in 0..3 {
+        provider.push(usage(70.0, 40.0));
+    }
+    for i in 1..=5 {
+        provider.push(usage(70.0 + 5.0 * i as f64, 40.0 + i as f64));
+    }
+
+    let mut notifications = Vec::new();
+    for _ in 0..8 {
+        for effect in step(&mut engine).await {
+            if let Effect::Notify(notification) = effect {
+                notifications.push(notification);
+            }
+        }
+    }
+
+    let gaps = gaps(&provider.fetch_times());
+    let idle_gap = gaps[1];
+    let burst_gap = gaps[gaps.len() - 1];
+    assert!(idle_gap > Duration::from_secs(180));
+    assert!(burst_gap < idle_gap);
+    assert!(engine.poller().current_state() == TemperatureState::Blazing);
+
+    // Crossing 80, 90 and 95% on the way up
+    assert!(notifications.len() == 3);
+    assert!(
+        engine
+            .state()
+            .forecasts
+            .iter()
+            .any(|f| f.rate_per_hour > 0.0)
+    );
+}
+
+#[tokio::test]
+async fn test_outage_then_recovery() {
+    let provider = ScriptedProvider::default();
+    let mut engine = engine(&provider);
+
+    provider.push(usage(10.0, 20.0));
+    for _ in 0..3 {
+        provider.push(Err(FetchError::network(
+            NetworkErrorKind::ConnectionRefused,
+            "connection refused",
+        )));
+    }
+    provider.push(usage(12.0, 20.0));
+
+    step(&mut engine).await;
+    for _ in 0..3 {
+        step(&mut engine).await;
+        assert!(engine.state().current_error.is_some());
+        // Last-known-good data survives the outage
+        assert!(engine.state().last_success.is_some());
+    }
+    step(&mut engine).await;
+
+    let gaps = gaps(&provider.fetch_times());
+    assert!(gaps[1..] == [10, 20, 40].map(Duration::from_secs));
+    assert!(engine.state().current_error.is_none());
+
+    // The outage stays visible in the diagnostics after recovering
+    let attempts: Vec<_> = engine.state().attempts.iter().collect();
+    assert!(attempts.len() == 5);
+    assert!(attempts[0].is_success() && attempts[4].is_success());
+    for (attempt, delay) in attempts[1..4].iter().zip([10, 20, 40]) {
+        assert!(attempt.error_kind.as_deref() == Some("connection_refused"));
+        assert!(attempt.retry_delay == Some(Duration::from_secs(delay)));
+    }
+    assert!(
+        attempts
+            .windows(2)
+            .all(|w| w[0].started_at < w[1].started_at)
+    );
+    assert!(engine.retry_state().last_delay() == Duration::from_secs(5));
+    assert!(engine.next_wake() == Some(engine.clock().now() + engine.poller().current_interval()));
+}
+
+#[tokio::test]
+async fn test_overage_keeps_polling() {
+    let provider = ScriptedProvider::default();
+    let mut engine = engine(&provider);
+
+    provider.push(usage(99.0, 60.0));
+    provider.push(usage(100.6, 60.0));
+    provider.push(usage(250.0, 61.0));
+    provider.push(usage(250.0, 61.0));
+
+    for _ in 0..4 {
+        step(&mut engine).await;
+        assert!(engine.state().current_error.is_none());
+    }
+
+    assert!(provider.fetch_times().len() == 4);
+    let_assert!(Some(success) = &engine.state().last_success);
+    assert!(success.metrics.is_over_limit());
+    assert!(engine.next_wake().is_some());
+}
+
+#[tokio::test]
+async fn test_config_error_waits_for_config_change() {
+    let provider = ScriptedProvider::default();
+    let mut engine = engine(&provider);
+
+    provider.push(Err(FetchError::missing_config("credentials.org_id")));
+    provider.push(usage(10.0, 20.0));
+
+    step(&mut engine).await;
+    let_assert!(Some(FetchError::Config { key, .. }) = &engine.state().current_error);
+    assert!(key == "credentials.org_id");
+    // No retries while the settings are missing
+    assert!(engine.next_wake().is_none());
+
+    // Reloading an identical config changes nothing
+    engine.handle(EngineInput::ConfigReloaded(Box::new(Ok(
+        AppConfig::default(),
+    ))));
+    assert!(engine.next_wake().is_none());
+
+    engine.clock().advance(Duration::from_secs(600));
+    let mut config = AppConfig::default();
+    config.credentials.org_id = Some("org".to_string());
+    engine.handle(EngineInput::ConfigReloaded(Box::new(Ok(config))));
+    assert!(engine.next_wake() == Some(engine.clock().now()));
+
+    step(&mut engine).await;
+    assert!(provider.fetch_times().len() == 2);
+    assert!(engine.state().current_error.is_none());
+}
+
+#[tokio::test]
+async fn test_rate_limit_storm_honors_retry_after() {
+    let provider = ScriptedProvider::default();
+    let mut engine = engine(&provider);
+
+    provider.push(rate_limited(Some(120)));
+    provider.push(rate_limited(Some(30)));
+    provider.push(rate_limited(None));
+    provider.push(rate_limited(Some(45)));
+    provider.push(usage(10.0, 20.0));
+
+    for _ in 0..5 {
+        step(&mut engine).await;
+    }
+
+    let gaps = gaps(&provider.fetch_times());
+    assert!(gaps == [120, 30, 300, 45].map(Duration::from_secs));
+    assert!(engine.state().current_error.is_none());
+}
+
+#[tokio::test]
+async fn test_jitter_spreads_out_clients() {
+    let mut config = AppConfig::default();
+    config.retry.jitter = JitterKind::Decorrelated;
+    config.poller.jitter = JitterKind::Full;
+
+    // Clients that lose the network together, then poll idle usage
+    let outage = |seed| {
+        let config = config.clone();
+        async move {
+            let provider = ScriptedProvider::default();
+            let mut engine = PollingEngine::new(provider.clone(), provider.clock.clone(), config)
+                .with_seed(seed);
+            for _ in 0..4 {
+                provider.push(Err(FetchError::network(
+                    NetworkErrorKind::ConnectionRefused,
+                    "connection refused",
+                )));
+            }
+            for _ in 0..3 {
+                provider.push(usage(10.0, 20.0));
+            }
+            for _ in 0..7 {
+                step(&mut engine).await;
+            }
+            gaps(&provider.fetch_times())
+        }
+    };
+
+    let first = outage(1).await;
+    assert!(first == outage(1).await);
+    assert!(first != outage(2).await);
+
+    let retry_min = Duration::from_secs(config.retry.min_delay_secs);
+    let retry_max = Duration::from_secs(config.retry.max_delay_secs);
+    assert!(
+        first[..4]
+            .iter()
+            .all(|gap| (retry_min..=retry_max).contains(gap))
+    );
+    // Polling intervals are only ever shortened
+    let poll_min = Duration::from_secs(config.poller.min_interval_secs);
+    assert!(first[4..].iter().all(|gap| *gap >= poll_min));
+}
+
+#[tokio::test]
+async fn test_daily_budget_counts_the_last_day_and_retries() {
+    let provider = ScriptedProvider::default();
+    let mut config = AppConfig::default();
+    config.poller.strategy = StrategyKind::DailyBudget;
+    config.poller.daily_requests = 3;
+
+    // Fetches from before a restart, further back than the activity history
+    let_assert!(Ok(mut store) = HistoryStore::open_in_memory("scripted", 90));
+    for hours in [12, 2] {
+        let_assert!(Ok(snapshot) = usage(10.0, 20.0));
+        let record = FetchRecord {
+            timestamp: provider.clock.wall_now() - chrono::Duration::hours(hours),
+            periods: snapshot.periods,
+            poller_state: TemperatureState::Cold,
+            interval: Duration::from_secs(8 * 3600),
+            transitions: Vec::new(),
+        };
+        assert!(store.record(&record).is_ok());
+    }
+    let mut engine =
+        PollingEngine::new(provider.clone(), provider.clock.clone(), config).with_history(store);
+
+    provider.push(usage(10.0, 20.0));
+    provider.push(Err(FetchError::network(
+        NetworkErrorKind::ConnectionRefused,
+        "connection refused",
+    )));
+
+    // Third fetch of the day: wait until the one from 12 hours ago ages out
+    step(&mut engine).await;
+    let hours = |h: u64| Duration::from_secs(h * 3600);
+    assert!(engine.next_wake() == Some(engine.clock().now() + hours(12)));
+
+    // The failed fetch counts too, so the retry waits for the next one to age out
+    step(&mut engine).await;
+    assert!(engine.state().current_error.is_some());
+    assert!(engine.next_wake() == Some(engine.clock().now() + hours(10)));
+}
+
+#[tokio::test]
+async fn test_fetches_right_after_reset() {
+    let provider = ScriptedProvider::default();
+    let resets_at = provider.clock.wall_now() + chrono::Duration::seconds(100);
+    let mut engine = engine(&provider);
+
+    let period = |utilization, resets_at| UsagePeriod {
+        utilization,
+        resets_at: Some(resets_at),
+    };
+    let snapshot = |five_hour| {
+        Ok(UsageSnapshot {
+            periods: BTreeMap::from([
+                ("five_hour".to_string(), five_hour),
+                (
+                    "seven_day".to_string(),
+                    period(20.0, "2025-11-29T00:00:00Z".parse().unwrap()),
+                ),
+            ]),
+            http_status: Some(200),
+        })
+    };
+    provider.push(snapshot(period(95.0, resets_at)));
+    provider.push(snapshot(period(
+        0.0,
+        resets_at + chrono::Duration::hours(5),
+    )));
+
+    step(&mut engine).await;
+    step(&mut engine).await;
+
+    // The idle interval is 180s+, but the fetch lands 30s after the reset
+    let gaps = gaps(&provider.fetch_times());
+    assert!(gaps == [Duration::from_secs(130)]);
+    assert!(engine.next_wake() > Some(engine.clock().now() + Duration::from_secs(130)));
+}
+
+#[tokio::test]
+async fn test_sleep_and_wake() {
+    let provider = ScriptedProvider::default();
+    let mut engine = engine(&provider);
+
+    provider.push(usage(10.0, 20.0));
+    step(&mut engine).await;
+    assert!(engine.next_wake().is_some());
+
+    let effects = engine.handle(EngineInput::System(SystemEvent::SystemSleep));
+    assert!(effects == [Effect::SleepUntil(None)]);
+
+    // Nothing is due while asleep, however long that lasts
+    engine.clock().advance(Duration::from_secs(8 * 3600));
+    assert!(engine.next_wake().is_none());
+    assert!(provider.fetch_times().len() == 1);
+
+    let effects = engine.handle(EngineInput::System(SystemEvent::SystemWake));
+    assert!(effects == [Effect::SleepUntil(Some(engine.clock().now()))]);
+
+    provider.push(usage(11.0, 20.0));
+    step(&mut engine).await;
+    let times = provider.fetch_times();
+    assert!(times.len() == 2);
+    assert!(times[1] - times[0] == Duration::from_secs(8 * 3600));
+}
+
+#[tokio::test]
+async fn test_refresh_now_interrupts_long_sleep() {
+    let provider = ScriptedProvider::default();
+    let mut engine = engine(&provider);
+
+    provider.push(usage(10.0, 20.0));
+    step(&mut engine).await;
+    let_assert!(Some(next_fetch) = engine.next_wake());
+    assert!(next_fetch - engine.clock().now() > Duration::from_secs(180));
+
+    engine.clock().advance(Duration::from_secs(30));
+    let effects = engine.handle(EngineInput::Command(PollCommand::RefreshNow));
+    assert!(
+        effects
+            == [
+                Effect::Render,
+                Effect::SleepUntil(Some(engine.clock().now()))
+            ]
+    );
+
+    provider.push(usage(11.0, 20.0));
+    step(&mut engine).await;
+    assert!(gaps(&provider.fetch_times()) == [Duration::from_secs(30)]);
+}
+
+#[tokio::test]
+async fn test_timed_pause_expires_and_fetches() {
+    let provider = ScriptedProvider::default();
+    let mut engine = engine(&provider);
+
+    provider.push(usage(10.0, 20.0));
+    step(&mut engine).await;
+
+    let hour = Duration::from_secs(3600);
+    engine.handle(EngineInput::Command(PollCommand::Pause(Some(hour))));
+    assert!(matches!(
+        engine.state().user_pause,
+        Some(UserPause::Until(_))
+    ));
+    // Only the end of the pause is due, not the regular fetch before it
+    assert!(engine.next_wake() == Some(engine.clock().now() + hour));
+
+    provider.push(usage(11.0, 20.0));
+    let effects = step(&mut engine).await;
+    assert!(effects.contains(&Effect::Render));
+    assert!(engine.state().user_pause.is_none());
+    assert!(gaps(&provider.fetch_times()) == [hour]);
+}
+
+#[tokio::test]
+async fn test_reset_backoff_closes_breaker_and_fetches() {
+    let provider = ScriptedProvider::default();
+    let mut engine = engine(&provider);
+
+    let threshold = engine.config().breaker.failure_threshold;
+    for _ in 0..threshold {
+        provider.push(Err(FetchError::auth("Invalid session key")));
+        step(&mut engine).await;
+    }
+    assert!(engine.breaker().state() == BreakerState::Open);
+    let probe_interval = Duration::from_secs(engine.config().breaker.probe_interval_secs);
+    assert!(engine.next_wake() == Some(engine.clock().now() + probe_interval));
+
+    engine.clock().advance(Duration::from_secs(60));
+    engine.handle(EngineInput::Command(PollCommand::ResetBackoff));
+    assert!(engine.breaker().state() == BreakerState::Closed);
+    assert!(engine.next_wake() == Some(engine.clock().now()));
+
+    provider.push(usage(10.0, 20.0));
+    step(&mut engine).await;
+    assert!(engine.state().current_error.is_none());
+    assert!(engine.breaker().state() == BreakerState::Closed);
+}
+
+#[tokio::test]
+async fn test_reload_of_invalid_config_sets_warning() {
+    let path = std::env::temp_dir().join(format!(
+        "usage-indicator-engine-reload-{}.toml",
+        std::process::id()
+    ));
+    std::fs::write(&path, "[poller]\nmin_interval_secs = 0\n").unwrap();
+
+    let provider = ScriptedProvider::default();
+    let mut engine = engine(&provider).with_loader(ConfigLoader::new(Some(path.clone())));
+
+    provider.push(usage(10.0, 20.0));
+    step(&mut engine).await;
+    let next_fetch = engine.next_wake();
+
+    engine.handle(EngineInput::Command(PollCommand::ReloadConfig));
+    let_assert!(Some(warning) = &engine.state().config_warning);
+    assert!(warning.contains("poller.min_interval_secs"));
+    // The running config and schedule are kept
+    assert!(*engine.config() == AppConfig::default());
+    assert!(engine.next_wake() == next_fetch);
+
+    std::fs::write(&path, "[poller]\nmin_interval_secs = 90\n").unwrap();
+    engine.handle(EngineInput::Command(PollCommand::ReloadConfig));
+    assert!(engine.state().config_warning.is_none());
+    assert!(engine.config().poller.min_interval_secs == 90);
+
+    std::fs::remove_file(&path).ok();
+}
+
+#[tokio::test]
+async fn test_startup_config_warning_clears_on_reload() {
+    let provider = ScriptedProvider::default();
+    let mut engine = engine(&provider)
+        .with_config_warning("config.toml:2: retry.min_delay_secs must be greater than 0".into());
+
+    provider.push(Err(FetchError::missing_config("credentials.org_id")));
+    step(&mut engine).await;
+    assert!(engine.state().config_warning.is_some());
+
+    let mut config = AppConfig::default();
+    config.credentials.org_id = Some("org".to_string());
+    engine.handle(EngineInput::ConfigReloaded(Box::new(Ok(config))));
+    assert!(engine.state().config_warning.is_none());
+    assert!(engine.next_wake() == Some(engine.clock().now()));
+}
@@ -1,9 +1,18 @@
 mod common;
 
 use assert2::{assert, let_assert};
-use common::MockClaudeApi;
+use chrono::{Duration, Utc};
+use common::{MockClaudeApi, TestClock, credentials, provider, step};
 use rstest::rstest;
-use usage_indicator_lib::{FetchError, fetch_usage_data_with_base_url};
+use serde_json::json;
+use std::future::Future;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use usage_indicator_lib::{
+    AppConfig, BreakerState, ClaudeProvider, Clock, CredentialsConfig, EngineInput, FetchError,
+    HttpConfig, MetricRole, NetworkErrorKind, Permille, PollingEngine, UsageProvider,
+    UsageSnapshot, fetch_usage_data_with_base_url,
+};
 
 #[rstest]
 #[case(15.0, 45.0)]
@@ -35,7 +44,9 @@ async fn test_auth_error_returns_auth_fetch_error() {
             .await;
 
     let_assert!(Err(error) = result);
-    assert!(matches!(error, FetchError::Auth(_)));
+    assert!(matches!(error, FetchError::Auth { .. }));
+    assert!(error.http_status() == Some(401));
+    assert!(error.kind_name() == "auth");
     assert!(!error.is_transient());
     assert!(error.category() == "Authentication Error");
 }
@@ -60,6 +71,90 @@ async fn test_rate_limit_error_returns_rate_limited_fetch_error() {
     assert!(error.category() == "Rate Limited");
 }
 
+#[rstest]
+#[case("retry-after", "120", 120)]
+#[case("retry-after", " 7 ", 7)]
+#[case("x-ratelimit-reset", "30", 30)]
+#[tokio::test]
+async fn test_rate_limit_honors_delta_seconds(
+    #[case] header: &str,
+    #[case] value: &str,
+    #[case] expected_secs: u64,
+) {
+    let mut mock_api = MockClaudeApi::new().await;
+    let _mock = mock_api.mock_rate_limit_with_header(header, value);
+
+    let result =
+        fetch_usage_data_with_base_url(&mock_api.url(), &mock_api.org_id, "test-session-key").await;
+
+    let_assert!(Err(FetchError::RateLimited { retry_after, .. }) = result);
+    assert!(retry_after == Some(expected_secs));
+}
+
+#[tokio::test]
+async fn test_rate_limit_honors_retry_after_http_date() {
+    let mut mock_api = MockClaudeApi::new().await;
+    let retry_at = Utc::now() + Duration::seconds(90);
+    let http_date = retry_at.format("%a, %d %b %Y %H:%M:%S GMT").to_string();
+    let _mock = mock_api.mock_rate_limit_with_header("retry-after", &http_date);
+
+    let result =
+        fetch_usage_data_with_base_url(&mock_api.url(), &mock_api.org_id, "test-session-key").await;
+
+    let_assert!(
+        Err(FetchError::RateLimited {
+            retry_after: Some(seconds),
+            ..
+        }) = result
+    );
+    // Allow for the clock ticking between building the header and parsing it
+    assert!((88..=90).contains(&seconds));
+}
+
+#[tokio::test]
+async fn test_rate_limit_retry_after_in_the_past_is_zero() {
+    let mut mock_api = MockClaudeApi::new().await;
+    let _mock =
+        mock_api.mock_rate_limit_with_header("retry-after", "Wed, 21 Oct 2015 07:28:00 GMT");
+
+    let result =
+        fetch_usage_data_with_base_url(&mock_api.url(), &mock_api.org_id, "test-session-key").await;
+
+    let_assert!(Err(FetchError::RateLimited { retry_after, .. }) = result);
+    assert!(retry_after == Some(0));
+}
+
+#[tokio::test]
+async fn test_rate_limit_honors_rfc3339_reset_header() {
+    let mut mock_api = MockClaudeApi::new().await;
+    let reset_at = (Utc::now() + Duration::seconds(600)).to_rfc3339();
+    let _mock =
+        mock_api.mock_rate_limit_with_header("anthropic-ratelimit-requests-reset", &reset_at);
+
+    let result =
+        fetch_usage_data_with_base_url(&mock_api.url(), &mock_api.org_id, "test-session-key").await;
+
+    let_assert!(
+        Err(FetchError::RateLimited {
+            retry_after: Some(seconds),
+            ..
+        }) = result
+    );
+    assert!((598..=600).contains(&seconds));
+}
+
+#[tokio::test]
+async fn test_rate_limit_ignores_malformed_retry_after() {
+    let mut mock_api = MockClaudeApi::new().await;
+    let _mock = mock_api.mock_rate_limit_with_header("retry-after", "soon");
+
+    let result =
+        fetch_usage_data_with_base_url(&mock_api.url(), &mock_api.org_id, "test-session-key").await;
+
+    let_assert!(Err(FetchError::RateLimited { retry_after, .. }) = result);
+    assert!(retry_after.is_none());
+}
+
 #[tokio::test]
 async fn test_server_error_returns_network_fetch_error() {
     let mut mock_api = MockClaudeApi::new().await;
@@ -69,9 +164,9 @@ async fn test_server_error_returns_network_fetch_error() {
         fetch_usage_data_with_base_url(&mock_api.url(), &mock_api.org_id, "test-session-key").await;
 
     let_assert!(Err(error) = result);
-    assert!(matches!(error, FetchError::Network(_)));
+    assert!(error.network_kind() == Some(NetworkErrorKind::HttpStatus(500)));
     assert!(error.is_transient());
-    assert!(error.category() == "Offline");
+    assert!(error.category() == "Server Error");
 }
 
 #[tokio::test]
@@ -89,8 +184,57 @@ async fn test_invalid_json_returns_parse_error() {
 }
 
 #[rstest]
-#[case(FetchError::Network("Connection failed".to_string()), true, "Offline")]
-#[case(FetchError::Auth("Invalid credentials".to_string()), false, "Authentication Error")]
+#[case(
+    FetchError::network(NetworkErrorKind::Timeout, "timed out"),
+    true,
+    "Offline"
+)]
+#[case(
+    FetchError::network(NetworkErrorKind::ConnectionRefused, "refused"),
+    true,
+    "Offline"
+)]
+#[case(
+    FetchError::network(NetworkErrorKind::Dns, "no such host"),
+    true,
+    "Offline"
+)]
+#[case(
+    FetchError::network(NetworkErrorKind::Connect, "tls connection reset"),
+    true,
+    "Offline"
+)]
+#[case(
+    FetchError::network(NetworkErrorKind::Tls, "bad certificate"),
+    false,
+    "TLS Error"
+)]
+#[case(
+    FetchError::network(NetworkErrorKind::HttpStatus(503), "cert"),
+    true,
+    "Server Error"
+)]
+#[case(
+    FetchError::network(NetworkErrorKind::HttpStatus(408), "slow"),
+    true,
+    "HTTP Error"
+)]
+#[case(
+    FetchError::network(NetworkErrorKind::HttpStatus(404), "not found"),
+    false,
+    "HTTP Error"
+)]
+#[case(
+    FetchError::network(NetworkErrorKind::BodyRead, "eof"),
+    true,
+    "Offline"
+)]
+#[case(
+    FetchError::network(NetworkErrorKind::InvalidRequest, "bad header"),
+    false,
+    "Request Error"
+)]
+#[case(FetchError::auth("Invalid credentials"), false, "Authentication Error")]
 #[case(
     FetchError::RateLimited {
         message: "Too many requests".to_string(),
@@ -100,6 +244,11 @@ async fn test_invalid_json_returns_parse_error() {
     "Rate Limited"
 )]
 #[case(FetchError::Parse("Invalid JSON".to_string()), false, "Parse Error")]
+#[case(
+    FetchError::missing_config("credentials.org_id"),
+    false,
+    "Not Configured"
+)]
 #[test]
 fn test_fetch_error_properties(
     #[case] error: FetchError,
@@ -123,11 +272,317 @@ async fn test_usage_data_deserialization() {
     // Verify all fields are properly deserialized
     assert!(data.five_hour.utilization == 25.0);
     assert!(data.seven_day.utilization == 75.0);
+    assert!(data.five_hour.resets_at == "2025-11-22T18:00:00Z".parse().ok());
     assert!(data.seven_day_oauth_apps.is_none());
-    assert!(data.seven_day_opus.utilization == 0.0);
+    let_assert!(Some(opus) = data.seven_day_opus);
+    assert!(opus.utilization == 0.0);
     assert!(data.iguana_necktie.is_none());
 }
 
+#[tokio::test]
+async fn test_usage_data_converts_to_snapshot() {
+    let mut mock_api = MockClaudeApi::new().await;
+    let _mock = mock_api.mock_success_response(25.4, 75.6);
+
+    let result =
+        fetch_usage_data_with_base_url(&mock_api.url(), &mock_api.org_id, "test-session-key").await;
+
+    let_assert!(Ok(data) = result);
+    let snapshot = UsageSnapshot::from(data);
+
+    // Null buckets are omitted from the snapshot
+    assert!(snapshot.periods.len() == 3);
+    assert!(snapshot.get("seven_day_oauth_apps").is_none());
+
+    let provider = ClaudeProvider::new(CredentialsConfig::default(), HttpConfig::default());
+    let_assert!(
+        Some((descriptor, period)) = snapshot.by_role(provider.metrics(), MetricRole::LongWindow)
+    );
+    assert!(descriptor.id == "seven_day");
+    assert!(period.utilization == 75.6);
+
+    let_assert!(Ok(metrics) = snapshot.usage_metrics(provider.metrics()));
+    assert!(metrics.five_hour() == Permille::from_tenths(254));
+    assert!(metrics.weekly() == Permille::from_tenths(756));
+}
+
+#[tokio::test]
+async fn test_unknown_buckets_are_tracked() {
+    let mut mock_api = MockClaudeApi::new().await;
+    let period = |utilization: f64| json!({"utilization": utilization, "resets_at": null});
+    let _mock = mock_api.mock_usage_body(json!({
+        "five_hour": period(10.0),
+        "seven_day": period(20.0),
+        "seven_day_oauth_apps": period(3.0),
+        "seven_day_opus": period(30.0),
+        "iguana_necktie": null,
+        "seven_day_sonnet": period(12.4),
+        "plan": "pro"
+    }));
+
+    let result =
+        fetch_usage_data_with_base_url(&mock_api.url(), &mock_api.org_id, "test-session-key").await;
+
+    let_assert!(Ok(data) = result);
+    assert!(data.other.contains_key("plan"));
+    let snapshot = UsageSnapshot::from(data);
+
+    // Fields that aren't usage periods are ignored
+    assert!(snapshot.periods.len() == 5);
+    assert!(snapshot.get("plan").is_none());
+
+    let provider = ClaudeProvider::new(CredentialsConfig::default(), HttpConfig::default());
+    let undescribed: Vec<_> = snapshot
+        .undescribed(provider.metrics())
+        .map(|(id, _)| id)
+        .collect();
+    assert!(undescribed == ["seven_day_sonnet"]);
+
+    let_assert!(Ok(metrics) = snapshot.usage_metrics(provider.metrics()));
+    assert!(metrics.get("seven_day_opus") == Some(Permille::from_whole(30)));
+    assert!(metrics.get("seven_day_oauth_apps") == Some(Permille::from_whole(3)));
+    assert!(metrics.get("seven_day_sonnet") == Some(Permille::from_tenths(124)));
+}
+
+#[tokio::test]
+async fn test_claude_provider_fetches_snapshot() {
+    let mut mock_api = MockClaudeApi::new().await;
+    let _mock = mock_api.mock_success_response(15.0, 45.0);
+
+    let provider = provider(&mock_api);
+
+    let_assert!(Ok(snapshot) = provider.fetch().await);
+    let_assert!(Some(period) = snapshot.get("five_hour"));
+    assert!(period.utilization == 15.0);
+}
+
+#[rstest]
+#[case::slightly_over(100.6, 45.0, 1006, 450, true)]
+#[case::deep_overage(250.0, 101.2, 2500, 1012, true)]
+#[case::negative_five_hour(-3.0, 45.0, 0, 450, false)]
+#[case::negative_weekly(15.0, -0.5, 150, 0, false)]
+#[tokio::test]
+async fn test_out_of_range_utilization_is_usable(
+    #[case] five_hour: f64,
+    #[case] weekly: f64,
+    #[case] five_hour_tenths: u16,
+    #[case] weekly_tenths: u16,
+    #[case] over_limit: bool,
+) {
+    let mut mock_api = MockClaudeApi::new().await;
+    let _mock = mock_api.mock_success_response(five_hour, weekly);
+
+    let provider = provider(&mock_api);
+
+    let_assert!(Ok(snapshot) = provider.fetch().await);
+    // Negative values are treated as unused; overage is kept as reported
+    let_assert!(Some(period) = snapshot.get("five_hour"));
+    assert!(period.utilization == five_hour.max(0.0));
+
+    let_assert!(Ok(metrics) = snapshot.usage_metrics(provider.metrics()));
+    assert!(metrics.five_hour() == Permille::from_tenths(five_hour_tenths));
+    assert!(metrics.weekly() == Permille::from_tenths(weekly_tenths));
+    assert!(metrics.is_over_limit() == over_limit);
+}
+
+#[rstest]
+#[case::renamed_bucket(json!({
+    "five_hour": {"utilization": 15.0, "resets_at": null},
+    "seven_day": {"utilization": 45.0, "resets_at": null},
+    "seven_day_claude_opus": {"utilization": 5.0, "resets_at": null}
+}), true)]
+#[case::dropped_required(json!({
+    "five_hour": {"utilization": 15.0, "resets_at": null},
+    "weekly": {"utilization": 45.0, "resets_at": null}
+}), false)]
+#[tokio::test]
+async fn test_claude_provider_tolerates_schema_drift(
+    #[case] body: serde_json::Value,
+    #[case] expect_ok: bool,
+) {
+    let mut mock_api = MockClaudeApi::new().await;
+    let _mock = mock_api.mock_usage_body(body.clone());
+
+    let dir =
+        std::env::temp_dir().join(format!("usage-drift-{}-{}", std::process::id(), expect_ok));
+    let _ = std::fs::remove_dir_all(&dir);
+    let provider = provider(&mock_api).with_drift_samples(Some(dir.clone()));
+
+    let result = provider.fetch().await;
+    if expect_ok {
+        let_assert!(Ok(snapshot) = result);
+        assert!(snapshot.get("seven_day_claude_opus").is_some());
+        assert!(snapshot.get("seven_day_opus").is_none());
+    } else {
+        let_assert!(Err(FetchError::Parse(message)) = result);
+        assert!(message.contains("missing `seven_day`"));
+    }
+
+    // The new shape is sampled once, parsed or not
+    provider.fetch().await.ok();
+    let samples: Vec<_> = std::fs::read_dir(&dir).unwrap().flatten().collect();
+    assert!(samples.len() == 1);
+    let saved = std::fs::read_to_string(samples[0].path()).unwrap();
+    assert!(serde_json::from_str::<serde_json::Value>(&saved).unwrap() == body);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_server_cookies_survive_between_polls_and_restarts() {
+    let mut mock_api = MockClaudeApi::new().await;
+    let path = format!("/api/organizations/{}/usage", mock_api.org_id);
+    let challenge = mock_api
+        .server
+        .mock("GET", path.as_str())
+        .match_header("cookie", "sessionKey=test-session-key")
+        .with_status(403)
+        .with_header("set-cookie", "cf_clearance=abc; Path=/; HttpOnly")
+        .with_body("Just a moment...")
+        .expect(1)
+        .create();
+    let cleared = mock_api
+        .server
+        .mock("GET", path.as_str())
+        .match_header("cookie", "sessionKey=test-session-key; cf_clearance=abc")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(
+            json!({
+                "five_hour": {"utilization": 15.0, "resets_at": null},
+                "seven_day": {"utilization": 45.0, "resets_at": null}
+            })
+            .to_string(),
+        )
+        .expect(2)
+        .create();
+
+    let dir = std::env::temp_dir().join(format!("usage-cookies-it-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let http = HttpConfig {
+        cookie_path: Some(dir.join("cookies.json")),
+        ..HttpConfig::default()
+    };
+    let credentials = credentials(&mock_api);
+    let provider = ClaudeProvider::with_base_url(mock_api.url(), credentials.clone())
+        .with_http_config(http.clone());
+
+    let_assert!(Err(FetchError::Auth { .. }) = provider.fetch().await);
+    let_assert!(Ok(_) = provider.fetch().await);
+
+    // A new provider, as after a restart, picks the cookie up from disk
+    let restarted =
+        ClaudeProvider::with_base_url(mock_api.url(), credentials).with_http_config(http);
+    let_assert!(Ok(_) = restarted.fetch().await);
+
+    challenge.assert();
+    cleared.assert();
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn test_reconfigured_http_settings_apply_without_restart() {
+    let mut mock_api = MockClaudeApi::new().await;
+    let path = format!("/api/organizations/{}/usage", mock_api.org_id);
+    let cleared = mock_api
+        .server
+        .mock("GET", path.as_str())
+        .match_header("cookie", "sessionKey=test-session-key; cf_clearance=abc")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(
+            json!({
+                "five_hour": {"utilization": 15.0, "resets_at": null},
+                "seven_day": {"utilization": 45.0, "resets_at": null}
+            })
+            .to_string(),
+        )
+        .expect(1)
+        .create();
+
+    let dir = std::env::temp_dir().join(format!("usage-cookies-reload-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let cookie_path = dir.join("cookies.json");
+    std::fs::write(
+        &cookie_path,
+        json!({"cf_clearance": {"value": "abc", "expires": null}}).to_string(),
+    )
+    .unwrap();
+
+    let mut provider = provider(&mock_api);
+    let_assert!(Err(_) = provider.fetch().await);
+
+    // Pointing `[http]` at the saved cookies takes effect on the next fetch
+    let config = AppConfig {
+        credentials: credentials(&mock_api),
+        http: HttpConfig {
+            cookie_path: Some(cookie_path),
+            ..HttpConfig::default()
+        },
+        ..AppConfig::default()
+    };
+    provider.reconfigure(&config);
+    let_assert!(Ok(_) = provider.fetch().await);
+
+    cleared.assert();
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn test_claude_provider_without_credentials_is_config_error() {
+    let mut mock_api = MockClaudeApi::new().await;
+    let _mock = mock_api.mock_success_response(15.0, 45.0);
+    let mut provider = ClaudeProvider::with_base_url(mock_api.url(), CredentialsConfig::default());
+
+    let_assert!(Err(error) = provider.fetch().await);
+    let_assert!(FetchError::Config { key, message } = &error);
+    assert!(key == "credentials.org_id");
+    assert!(message.contains("CLAUDE_ORG_ID"));
+    assert!(!error.is_transient());
+
+    // Credentials from a reloaded config are used without a restart
+    let config = AppConfig {
+        credentials: credentials(&mock_api),
+        ..AppConfig::default()
+    };
+    provider.reconfigure(&config);
+    let_assert!(Ok(_) = provider.fetch().await);
+}
+
+#[rstest]
+#[case::empty_session_key(Some("org"), Some("  "), "credentials.session_key")]
+#[case::slash_in_org_id(Some("org/../admin"), Some("key"), "credentials.org_id")]
+#[case::newline_in_session_key(Some("org"), Some("key\nX-Injected: 1"), "credentials.session_key")]
+#[case::semicolon_in_session_key(Some("org"), Some("key; other=1"), "credentials.session_key")]
+#[tokio::test]
+async fn test_invalid_credentials_are_config_errors(
+    #[case] org_id: Option<&str>,
+    #[case] session_key: Option<&str>,
+    #[case] expected_key: &str,
+) {
+    let mock_api = MockClaudeApi::new().await;
+    let provider = ClaudeProvider::with_base_url(
+        mock_api.url(),
+        CredentialsConfig {
+            org_id: org_id.map(str::to_string),
+            session_key: session_key.map(str::to_string),
+        },
+    );
+
+    let_assert!(Err(FetchError::Config { key, .. }) = provider.fetch().await);
+    assert!(key == expected_key);
+}
+
+#[test]
+fn test_snapshot_without_required_metrics_is_parse_error() {
+    let provider = ClaudeProvider::new(CredentialsConfig::default(), HttpConfig::default());
+    let snapshot = UsageSnapshot::default();
+
+    let_assert!(Err(error) = snapshot.usage_metrics(provider.metrics()));
+    assert!(matches!(error, FetchError::Parse(_)));
+}
+
 #[tokio::test]
 async fn test_fetch_with_empty_org_id() {
     let mock_api = MockClaudeApi::new().await;
@@ -135,7 +590,166 @@ async fn test_fetch_with_empty_org_id() {
     let result = fetch_usage_data_with_base_url(&mock_api.url(), "", "test-session-key").await;
 
     // Should succeed with empty org_id (API will handle validation)
-    // The mock won't match, so it should return a network error
+    // The mock won't match, so the server answers 501
     let_assert!(Err(error) = result);
-    assert!(matches!(error, FetchError::Network(_)));
+    assert!(error.network_kind() == Some(NetworkErrorKind::HttpStatus(501)));
+}
+
+#[tokio::test]
+async fn test_connection_refused_is_classified_with_source() {
+    // Bind and drop a listener so the port is very likely closed
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+
+    let result =
+        fetch_usage_data_with_base_url(&format!("http://127.0.0.1:{}", port), "org", "key").await;
+
+    let_assert!(Err(error) = result);
+    assert!(error.network_kind() == Some(NetworkErrorKind::ConnectionRefused));
+    assert!(error.is_transient());
+    assert!(std::error::Error::source(&error).is_some());
+}
+
+/// Handle every connection to a local port with `respond`, returning the port
+async fn serve<F, Fut>(respond: F) -> u16
+where
+    F: Fn(TcpStream) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(respond(stream));
+        }
+    });
+    port
+}
+
+#[tokio::test]
+async fn test_connection_reset_is_a_connect_error() {
+    let port = serve(|mut stream| async move {
+        // Read the request, then reset the connection instead of answering
+        let _ = stream.read(&mut [0; 1024]).await;
+        stream.set_linger(Some(std::time::Duration::ZERO)).unwrap();
+    })
+    .await;
+
+    let result =
+        fetch_usage_data_with_base_url(&format!("http://127.0.0.1:{}", port), "org", "key").await;
+
+    let_assert!(Err(error) = result);
+    assert!(error.network_kind() == Some(NetworkErrorKind::Connect));
+    assert!(error.is_transient());
+}
+
+#[tokio::test]
+async fn test_unresolvable_host_is_a_dns_error() {
+    let result =
+        fetch_usage_data_with_base_url("http://usage-indicator-test.invalid", "org", "key").await;
+
+    let_assert!(Err(error) = result);
+    assert!(error.network_kind() == Some(NetworkErrorKind::Dns));
+    assert!(error.is_transient());
+}
+
+#[tokio::test]
+async fn test_failed_handshake_is_a_tls_error() {
+    let port = serve(|mut stream| async move {
+        // Answer the TLS client hello in plain HTTP
+        let _ = stream.read(&mut [0; 1024]).await;
+        let _ = stream
+            .write_all(b"HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\n\r\n")
+            .await;
+        let _ = stream.read(&mut [0; 1024]).await;
+    })
+    .await;
+
+    let result =
+        fetch_usage_data_with_base_url(&format!("https://127.0.0.1:{}", port), "org", "key").await;
+
+    let_assert!(Err(error) = result);
+    assert!(error.network_kind() == Some(NetworkErrorKind::Tls));
+    assert!(!error.is_transient());
+}
+
+#[tokio::test]
+async fn test_circuit_breaker_stops_retrying_a_dead_session_key() {
+    let mut mock_api = MockClaudeApi::new().await;
+    let path = format!("/api/organizations/{}/usage", mock_api.org_id);
+    let rejected = mock_api
+        .server
+        .mock("GET", path.as_str())
+        .match_header("cookie", "sessionKey=dead-session-key")
+        .with_status(401)
+        .with_body(json!({"error": "Invalid session key"}).to_string())
+        .expect(6)
+        .create();
+    let accepted = mock_api
+        .server
+        .mock("GET", path.as_str())
+        .match_header("cookie", "sessionKey=fresh-session-key")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(
+            json!({
+                "five_hour": {"utilization": 15.0, "resets_at": null},
+                "seven_day": {"utilization": 45.0, "resets_at": null}
+            })
+            .to_string(),
+        )
+        .expect(1)
+        .create();
+
+    let mut config = AppConfig {
+        credentials: CredentialsConfig {
+            org_id: Some(mock_api.org_id.clone()),
+            session_key: Some("dead-session-key".to_string()),
+        },
+        http: HttpConfig {
+            persist_cookies: false,
+            ..HttpConfig::default()
+        },
+        ..AppConfig::default()
+    };
+    let provider = ClaudeProvider::with_base_url(mock_api.url(), config.credentials.clone())
+        .with_http_config(config.http.clone());
+    let clock = TestClock::new();
+    let mut engine = PollingEngine::new(provider, clock.clone(), config.clone());
+    let probe_interval = std::time::Duration::from_secs(config.breaker.probe_interval_secs);
+
+    // Retried at the minimum delay until the threshold is reached
+    for _ in 0..4 {
+        step(&mut engine).await;
+        assert!(engine.breaker().state() == BreakerState::Closed);
+        assert!(engine.next_wake() == Some(clock.now() + std::time::Duration::from_secs(5)));
+    }
+    step(&mut engine).await;
+    assert!(engine.breaker().state() == BreakerState::Open);
+    assert!(engine.next_wake() == Some(clock.now() + probe_interval));
+
+    // A failed probe keeps it open for another interval
+    step(&mut engine).await;
+    let_assert!(Some(FetchError::Auth { .. }) = &engine.state().current_error);
+    assert!(engine.breaker().state() == BreakerState::Open);
+    assert!(engine.next_wake() == Some(clock.now() + probe_interval));
+    let_assert!(Some(attempt) = engine.state().attempts.iter().last());
+    assert!(attempt.http_status == Some(401));
+    assert!(attempt.retry_delay == Some(probe_interval));
+
+    // New credentials close it and fetch right away
+    config.credentials.session_key = Some("fresh-session-key".to_string());
+    engine.handle(EngineInput::ConfigReloaded(Box::new(Ok(config))));
+    assert!(engine.breaker().state() == BreakerState::Closed);
+    assert!(engine.next_wake() == Some(clock.now()));
+
+    step(&mut engine).await;
+    assert!(engine.state().current_error.is_none());
+    let_assert!(Some(attempt) = engine.state().attempts.iter().last());
+    assert!(attempt.http_status == Some(200));
+    rejected.assert();
+    accepted.assert();
 }